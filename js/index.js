@@ -1,4 +1,5 @@
 const leftButton = 0;
+const middleButton = 1;
 let isHidden = false;
 const canvasContainer = document.querySelector('.canvasContainer');
 const canvas = document.querySelector('canvas');
@@ -7,10 +8,58 @@ canvas.height = boundingRect.height
 canvas.width = boundingRect.width
 
 const cords = document.querySelector(".cords");
+const createContainer = document.querySelector(".createContainer");
 const rulesContainer = document.querySelector(".rulesContainer");
+const editContainer = document.querySelector(".editContainer");
+const transformContainer = document.querySelector(".transformContainer");
 
 const removeRelationsButton = document.querySelector("#RemoveRelations");
+const lengthDimensionButton = document.querySelector("#LengthDimension");
 const makeBezier = document.querySelector("#MakeBezier");
+const splitParameter = document.querySelector("#SplitParameter");
+const splitAtButton = document.querySelector("#SplitAt");
+const splitParts = document.querySelector("#SplitParts");
+const splitIntoButton = document.querySelector("#SplitInto");
+const mergeAlongEdgeButton = document.querySelector("#MergeAlongEdge");
+const convexHullButton = document.querySelector("#ConvexHull");
+const convexDecompositionButton = document.querySelector("#ConvexDecomposition");
+const convexityLabel = document.querySelector("#ConvexityLabel");
+const offsetDistance = document.querySelector("#OffsetDistance");
+const offsetJoin = document.querySelector("#OffsetJoin");
+const miterLimit = document.querySelector("#MiterLimit");
+const offsetButton = document.querySelector("#OffsetPolygon");
+const simplifyTolerance = document.querySelector("#SimplifyTolerance");
+const simplifyMethod = document.querySelector("#SimplifyMethod");
+const simplifyButton = document.querySelector("#SimplifyPolygon");
+const rotateAngle = document.querySelector("#RotateAngle");
+const scaleX = document.querySelector("#ScaleX");
+const scaleY = document.querySelector("#ScaleY");
+const shearX = document.querySelector("#ShearX");
+const shearY = document.querySelector("#ShearY");
+const mirrorAngle = document.querySelector("#MirrorAngle");
+const pivotX = document.querySelector("#PivotX");
+const pivotY = document.querySelector("#PivotY");
+const rotateButton = document.querySelector("#Rotate");
+const scaleButton = document.querySelector("#Scale");
+const shearButton = document.querySelector("#Shear");
+const mirrorButton = document.querySelector("#Mirror");
+const mirrorHorizontalButton = document.querySelector("#MirrorHorizontal");
+const mirrorVerticalButton = document.querySelector("#MirrorVertical");
+const setPivotButton = document.querySelector("#SetPivot");
+const removeLastPointButton = document.querySelector("#RemoveLastPoint");
+const cancelPolygonButton = document.querySelector("#CancelPolygon");
+const finishPathButton = document.querySelector("#FinishPath");
+const shapeTool = document.querySelector("#ShapeTool");
+const shapeSides = document.querySelector("#ShapeSides");
+const freehandTolerance = document.querySelector("#FreehandTolerance");
+const fitCurves = document.querySelector("#FitCurves");
+const toggleClosedButton = document.querySelector("#ToggleClosed");
+const deleteSelectionButton = document.querySelector("#DeleteSelection");
+const cornerSize = document.querySelector("#CornerSize");
+const cornerStyle = document.querySelector("#CornerStyle");
+const roundCornersButton = document.querySelector("#RoundCorners");
+const angleDimensionButton = document.querySelector("#AngleDimension");
+const clearPivotButton = document.querySelector("#ClearPivot");
 
 const radioCreate = document.querySelector("#Edit");
 const radioEdit = document.querySelector("#Highlight");
@@ -27,6 +76,36 @@ const bresenhamButton = document.querySelector("#Algorithm");
 
 const lengthSelector = document.querySelector('#LengthSelector');
 const isLengthConst = document.querySelector('#IsConst');
+const vertexMeanCenter = document.querySelector('#VertexMeanCenter');
+const showTriangulation = document.querySelector('#ShowTriangulation');
+const sharedTopology = document.querySelector('#SharedTopology');
+const snapGrid = document.querySelector('#SnapGrid');
+const gridSize = document.querySelector('#GridSize');
+const snapVertices = document.querySelector('#SnapVertices');
+const snapMidpoints = document.querySelector('#SnapMidpoints');
+const snapEdges = document.querySelector('#SnapEdges');
+const snapIntersections = document.querySelector('#SnapIntersections');
+const snapAngles = document.querySelector('#SnapAngles');
+const unitSelect = document.querySelector('#Unit');
+const pixelsPerUnit = document.querySelector('#PixelsPerUnit');
+const layerSelect = document.querySelector('#LayerSelect');
+const layerName = document.querySelector('#LayerName');
+const layerVisible = document.querySelector('#LayerVisible');
+const layerLocked = document.querySelector('#LayerLocked');
+const addLayerButton = document.querySelector('#AddLayer');
+const removeLayerButton = document.querySelector('#RemoveLayer');
+const renameLayerButton = document.querySelector('#RenameLayer');
+const layerUpButton = document.querySelector('#LayerUp');
+const layerDownButton = document.querySelector('#LayerDown');
+const moveToLayerButton = document.querySelector('#MoveToLayer');
+const fitToSceneButton = document.querySelector('#FitToScene');
+const resetViewButton = document.querySelector('#ResetView');
+const exportJsonButton = document.querySelector('#ExportJson');
+const exportObjButton = document.querySelector('#ExportObj');
+const exportSvgButton = document.querySelector('#ExportSvg');
+const saveSceneButton = document.querySelector('#SaveScene');
+const loadSceneButton = document.querySelector('#LoadScene');
+const sceneFile = document.querySelector('#SceneFile');
 
 helpExitButton.onclick = () => {
     container.classList.remove("modalShown");
@@ -38,6 +117,14 @@ helpButton.onclick = () => {
     modalContainer.classList.add("modalShown");
 }
 
+const downloadFile = (name, content) => {
+    const link = document.createElement('a');
+    link.href = URL.createObjectURL(new Blob([content], { type: 'text/plain' }));
+    link.download = name;
+    link.click();
+    URL.revokeObjectURL(link.href);
+}
+
 const setPressedButton = (radioObject) => {
     radioCreate.classList.remove("activeMode");
     radioEdit.classList.remove("activeMode");
@@ -53,6 +140,20 @@ async function setHandlers() {
     const elemLeft = canvas.offsetLeft + canvas.clientLeft;
     const elemTop = canvas.offsetTop + canvas.clientTop;
 
+    // the editor rolls a failed edit back itself, the user only has to be told why it did not happen
+    const runCommand = (command) => {
+        try {
+            return command();
+        } catch (error) {
+            alert(error.message);
+        }
+    };
+
+    const updateConvexityLabel = () => {
+        const isConvex = canvasRef.is_selected_convex();
+        convexityLabel.innerHTML = isConvex === undefined ? "" : (isConvex ? "polygon is convex" : "polygon is concave");
+    };
+
     window.onresize = () => {
         const currentBoundingRect = canvasContainer.getBoundingClientRect();
         canvas.height = currentBoundingRect.height
@@ -65,59 +166,129 @@ async function setHandlers() {
             y = event.pageY - elemTop;
         event.preventDefault();
         console.log('right');
-        canvasRef.on_right_click(x, y);
-        console.log('right');
+        runCommand(() => canvasRef.on_right_click(x, y));
     };
 
+    let isPanning = false;
+
     canvas.onmousedown = (event) => {
         const x = event.pageX - elemLeft,
             y = event.pageY - elemTop;
         console.log('mouse down', x, y);
-        if(event.button == 0)
-            canvasRef.on_down_click(x,y);
+        if(event.button == leftButton)
+            canvasRef.on_down_click(x, y, event.shiftKey);
+        if(event.button == middleButton) {
+            event.preventDefault();
+            isPanning = true;
+        }
     }
 
+    window.addEventListener('mouseup', (event) => {
+        if(event.button == middleButton)
+            isPanning = false;
+        if(event.button == leftButton && event.target !== canvas)
+            canvasRef.end_sketch();
+    });
+
+    canvas.onwheel = (event) => {
+        const x = event.pageX - elemLeft,
+            y = event.pageY - elemTop;
+        event.preventDefault();
+        canvasRef.on_wheel(x, y, event.deltaY);
+    };
+
     canvas.onclick = (event) => {
         const x = event.pageX - elemLeft,
             y = event.pageY - elemTop;
         console.log('mouse up',x,y);
-        canvasRef.on_left_click(x, y);
+        runCommand(() => canvasRef.on_left_click(x, y, event.shiftKey));
+        updateConvexityLabel();
     };
 
     canvas.onmousemove = (event) => {
         const x = event.pageX - elemLeft,
             y = event.pageY - elemTop;
 
-        cords.innerHTML = `x: ${x.toFixed(0)} y: ${y.toFixed(0)}`;
-        canvasRef.on_move_mouse(x, y);
+        if(isPanning) {
+            canvasRef.pan(event.movementX, event.movementY);
+            return;
+        }
+        const [worldX, worldY] = canvasRef.screen_to_world(x, y);
+        cords.innerHTML = `x: ${worldX.toFixed(0)} y: ${worldY.toFixed(0)}`;
+        runCommand(() => canvasRef.on_move_mouse(x, y));
     };
 
-    radioCreate.onclick = () => {
-        console.log('radion click');
+    const showCreateControls = () => {
         setPressedButton(radioCreate);
+        createContainer.classList.remove("settingsHidden");
         rulesContainer.classList.add("settingsHidden");
-        canvasRef.set_create_state();
+        editContainer.classList.add("settingsHidden");
+        transformContainer.classList.add("settingsHidden");
     };
 
-    radioEdit.onclick = () => {
-        console.log('highlight click');
+    const showEditControls = () => {
         setPressedButton(radioEdit);
+        createContainer.classList.add("settingsHidden");
         rulesContainer.classList.add("settingsHidden");
-        canvasRef.set_edit_state();
+        editContainer.classList.remove("settingsHidden");
+        transformContainer.classList.remove("settingsHidden");
     };
 
-    radioRules.onclick = () => {
-        console.log('rules click');
+    const showRulesControls = () => {
         setPressedButton(radioRules);
+        createContainer.classList.add("settingsHidden");
         rulesContainer.classList.remove("settingsHidden");
+        editContainer.classList.add("settingsHidden");
+        transformContainer.classList.remove("settingsHidden");
         isLengthConst.checked = false;
         lengthSelector.value = undefined;
+    };
+
+    radioCreate.onclick = () => {
+        console.log('radion click');
+        showCreateControls();
+        canvasRef.set_create_state();
+    };
+
+    radioEdit.onclick = () => {
+        console.log('highlight click');
+        showEditControls();
+        canvasRef.set_edit_state();
+    };
+
+    radioRules.onclick = () => {
+        console.log('rules click');
+        showRulesControls();
         canvasRef.set_rules_state();
     }
+
+    document.onkeydown = (event) => {
+        if(event.target instanceof HTMLInputElement || event.target instanceof HTMLSelectElement)
+            return;
+        const command = runCommand(() => canvasRef.on_key_down(event.key, event.ctrlKey || event.metaKey, event.shiftKey, event.altKey));
+        if(command === undefined)
+            return;
+        console.log('key command', command);
+        event.preventDefault();
+        if(command == 'create_mode')
+            showCreateControls();
+        if(command == 'edit_mode')
+            showEditControls();
+        if(command == 'rules_mode')
+            showRulesControls();
+        sharedTopology.checked = canvasRef.get_shared_topology();
+        showLayers();
+        updateConvexityLabel();
+    };
     
     removeRelationsButton.onclick = () => {
         console.log('removeRelations click');
-        canvasRef.remove_relations();
+        runCommand(() => canvasRef.remove_relations());
+    };
+
+    lengthDimensionButton.onclick = () => {
+        console.log('length dimension click');
+        runCommand(() => canvasRef.toggle_length_dimensions());
     };
 
     sceneButton.onclick = () => {
@@ -132,17 +303,313 @@ async function setHandlers() {
 
     makeBezier.onclick = () => {
         console.log('Bezier click');
-        canvasRef.make_bezier();
+        runCommand(() => canvasRef.make_bezier());
+    }
+
+    splitAtButton.onclick = () => {
+        console.log('split at click');
+        runCommand(() => canvasRef.split_selected_lines_at(Number(splitParameter.value)));
+    }
+
+    splitIntoButton.onclick = () => {
+        console.log('split into click');
+        runCommand(() => canvasRef.split_selected_lines(Number(splitParts.value)));
+    }
+
+    mergeAlongEdgeButton.onclick = () => {
+        console.log('merge along edge click');
+        if (runCommand(() => canvasRef.merge_along_selected_line()) === false) {
+            console.log('no polygon shares the selected edge');
+        }
+        updateConvexityLabel();
+    }
+
+    convexHullButton.onclick = () => {
+        console.log('convex hull click');
+        canvasRef.make_convex_hull();
+        updateConvexityLabel();
+    }
+
+    convexDecompositionButton.onclick = () => {
+        console.log('convex decomposition click');
+        runCommand(() => canvasRef.decompose_convex());
+        updateConvexityLabel();
+    }
+
+    offsetButton.onclick = () => {
+        console.log('offset click', offsetDistance.value, offsetJoin.value, miterLimit.value);
+        runCommand(() => canvasRef.offset_polygon(Number(offsetDistance.value), offsetJoin.value, Number(miterLimit.value)));
+        updateConvexityLabel();
+    }
+
+    simplifyButton.onclick = () => {
+        console.log('simplify click', simplifyMethod.value, simplifyTolerance.value);
+        const removed = runCommand(() => canvasRef.simplify_polygon(simplifyMethod.value, Number(simplifyTolerance.value)));
+        console.log('removed vertices', removed);
+        updateConvexityLabel();
+    }
+
+    rotateButton.onclick = () => {
+        console.log('rotate click', rotateAngle.value);
+        runCommand(() => canvasRef.rotate_polygon(Number(rotateAngle.value)));
+    }
+
+    scaleButton.onclick = () => {
+        console.log('scale click', scaleX.value, scaleY.value);
+        runCommand(() => canvasRef.scale_polygon(Number(scaleX.value), Number(scaleY.value)));
+    }
+
+    shearButton.onclick = () => {
+        console.log('shear click', shearX.value, shearY.value);
+        runCommand(() => canvasRef.shear_polygon(Number(shearX.value), Number(shearY.value)));
+    }
+
+    mirrorButton.onclick = () => {
+        console.log('mirror click', mirrorAngle.value);
+        runCommand(() => canvasRef.mirror_polygon(Number(mirrorAngle.value)));
+    }
+
+    mirrorHorizontalButton.onclick = () => {
+        console.log('mirror horizontal click');
+        runCommand(() => canvasRef.mirror_polygon(0));
+    }
+
+    mirrorVerticalButton.onclick = () => {
+        console.log('mirror vertical click');
+        runCommand(() => canvasRef.mirror_polygon(90));
+    }
+
+    setPivotButton.onclick = () => {
+        console.log('set pivot click', pivotX.value, pivotY.value);
+        canvasRef.set_pivot(Number(pivotX.value), Number(pivotY.value));
+    }
+
+    clearPivotButton.onclick = () => {
+        console.log('clear pivot click');
+        canvasRef.clear_pivot();
+    }
+
+    removeLastPointButton.onclick = () => {
+        console.log('remove last point click');
+        canvasRef.remove_last_point();
+    }
+
+    cancelPolygonButton.onclick = () => {
+        console.log('cancel polygon click');
+        canvasRef.cancel_polygon();
+    }
+
+    finishPathButton.onclick = () => {
+        console.log('finish path click');
+        canvasRef.finish_path();
+    }
+
+    const updateShapeTool = () => {
+        console.log('shape tool change', shapeTool.value, shapeSides.value);
+        runCommand(() => canvasRef.set_shape_tool(shapeTool.value, Number(shapeSides.value)));
+    }
+    shapeTool.onchange = updateShapeTool;
+    shapeSides.onchange = updateShapeTool;
+
+    const updateFreehand = () => {
+        console.log('freehand change', freehandTolerance.value, fitCurves.checked);
+        canvasRef.set_freehand(Number(freehandTolerance.value), fitCurves.checked);
+    }
+    freehandTolerance.onchange = updateFreehand;
+    fitCurves.onchange = updateFreehand;
+
+    toggleClosedButton.onclick = () => {
+        console.log('toggle closed click');
+        runCommand(() => canvasRef.toggle_closed());
+        updateConvexityLabel();
+    }
+
+    deleteSelectionButton.onclick = () => {
+        console.log('delete selection click');
+        runCommand(() => canvasRef.delete_selection());
+    }
+
+    roundCornersButton.onclick = () => {
+        console.log('round corners click', cornerStyle.value, cornerSize.value);
+        runCommand(() => canvasRef.round_selected_points(cornerStyle.value, Number(cornerSize.value)));
+    }
+
+    angleDimensionButton.onclick = () => {
+        console.log('angle dimension click');
+        runCommand(() => canvasRef.toggle_angle_dimensions());
     }
 
     lengthSelector.onchange = (event) => {
         console.log('set new length', event.target.value);
-        canvasRef.set_line_length();
+        runCommand(() => canvasRef.set_line_length());
     }
 
     isLengthConst.onchange = (event) => {
         console.log('const change', event.target.checked);
-        canvasRef.set_const_state();
+        runCommand(() => canvasRef.set_const_state());
+    }
+
+    vertexMeanCenter.onchange = (event) => {
+        console.log('centroid mode change', event.target.checked);
+        canvasRef.set_centroid_mode(event.target.checked);
+    }
+
+    showTriangulation.onchange = (event) => {
+        console.log('triangulation change', event.target.checked);
+        canvasRef.set_show_triangulation(event.target.checked);
+    }
+
+    sharedTopology.onchange = (event) => {
+        console.log('shared topology change', event.target.checked);
+        canvasRef.set_shared_topology(event.target.checked);
+    }
+
+    const updateGridSnapping = () => {
+        console.log('grid snapping change', snapGrid.checked, gridSize.value);
+        canvasRef.set_snap_grid(snapGrid.checked, Number(gridSize.value));
+    };
+    [snapGrid, gridSize].forEach((input) => input.onchange = updateGridSnapping);
+
+    [[snapVertices, 'vertices'], [snapMidpoints, 'midpoints'], [snapEdges, 'edges'], [snapIntersections, 'intersections'], [snapAngles, 'angles']]
+        .forEach(([input, target]) => input.onchange = () => {
+            console.log('snapping change', target, input.checked);
+            canvasRef.set_snap_to(target, input.checked);
+        });
+
+    const showUnits = () => {
+        unitSelect.value = canvasRef.get_unit();
+        pixelsPerUnit.value = canvasRef.get_pixels_per_unit();
+    };
+
+    unitSelect.onchange = () => {
+        console.log('unit change', unitSelect.value);
+        canvasRef.set_units(unitSelect.value, 0);
+        showUnits();
+    }
+
+    pixelsPerUnit.onchange = () => {
+        console.log('pixels per unit change', pixelsPerUnit.value);
+        canvasRef.set_units(unitSelect.value, Number(pixelsPerUnit.value));
+        showUnits();
+    }
+
+    const showLayers = () => {
+        const layers = JSON.parse(canvasRef.get_layers());
+        layerSelect.innerHTML = '';
+        // the top layer is listed first
+        layers.reverse().forEach((layer) => {
+            const option = document.createElement('option');
+            option.value = layer.id;
+            option.textContent = layer.name;
+            option.selected = layer.active;
+            layerSelect.appendChild(option);
+        });
+        const active = layers.find((layer) => layer.active);
+        if (active !== undefined) {
+            layerName.value = active.name;
+            layerVisible.checked = active.visible;
+            layerLocked.checked = active.locked;
+        }
+    };
+
+    const runLayerAction = (action) => {
+        runCommand(() => action(Number(layerSelect.value)));
+        showLayers();
+    };
+
+    layerSelect.onchange = () => {
+        console.log('layer change', layerSelect.value);
+        runLayerAction((id) => canvasRef.set_active_layer(id));
+    }
+
+    layerVisible.onchange = (event) => {
+        console.log('layer visible change', event.target.checked);
+        runLayerAction((id) => canvasRef.set_layer_visible(id, event.target.checked));
+    }
+
+    layerLocked.onchange = (event) => {
+        console.log('layer locked change', event.target.checked);
+        runLayerAction((id) => canvasRef.set_layer_locked(id, event.target.checked));
+    }
+
+    addLayerButton.onclick = () => {
+        console.log('add layer click');
+        canvasRef.add_layer(layerName.value);
+        showLayers();
+    }
+
+    removeLayerButton.onclick = () => {
+        console.log('remove layer click');
+        runLayerAction((id) => canvasRef.remove_layer(id));
+    }
+
+    renameLayerButton.onclick = () => {
+        console.log('rename layer click');
+        runLayerAction((id) => canvasRef.rename_layer(id, layerName.value));
+    }
+
+    layerUpButton.onclick = () => {
+        console.log('layer up click');
+        runLayerAction((id) => canvasRef.move_layer(id, 1));
+    }
+
+    layerDownButton.onclick = () => {
+        console.log('layer down click');
+        runLayerAction((id) => canvasRef.move_layer(id, -1));
+    }
+
+    moveToLayerButton.onclick = () => {
+        console.log('move to layer click');
+        runLayerAction((id) => canvasRef.move_selection_to_layer(id));
+    }
+
+    showLayers();
+
+    fitToSceneButton.onclick = () => {
+        console.log('fit to scene click');
+        canvasRef.fit_to_scene();
+    }
+
+    resetViewButton.onclick = () => {
+        console.log('reset view click');
+        canvasRef.reset_view();
+    }
+
+    exportJsonButton.onclick = () => {
+        console.log('export json click');
+        downloadFile('triangles.json', canvasRef.export_triangles_json());
+    }
+
+    exportObjButton.onclick = () => {
+        console.log('export obj click');
+        downloadFile('triangles.obj', canvasRef.export_triangles_obj());
+    }
+
+    exportSvgButton.onclick = () => {
+        console.log('export svg click');
+        downloadFile('scene.svg', canvasRef.export_svg());
+    }
+
+    saveSceneButton.onclick = () => {
+        console.log('save scene click');
+        downloadFile('scene.json', canvasRef.save_scene());
+    }
+
+    loadSceneButton.onclick = () => {
+        console.log('load scene click');
+        sceneFile.click();
+    }
+
+    sceneFile.onchange = async () => {
+        const file = sceneFile.files[0];
+        sceneFile.value = '';
+        if (file === undefined) {
+            return;
+        }
+        const json = await file.text();
+        runCommand(() => canvasRef.load_scene(json));
+        showUnits();
+        showLayers();
     }
 }
 
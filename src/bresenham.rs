@@ -10,8 +10,10 @@ impl Polygon {
         self.lines
             .iter()
             .for_each(|line| {
-                let p1 = self.get_point_by_id(line.points.0);
-                let p2 = self.get_point_by_id(line.points.1);
+                let (p1, p2) = match self.get_line_cords(line) {
+                    Ok(cords) => cords,
+                    Err(_) => return
+                };
                 draw_bresenham_line(context, p1, p2);
             });
 
@@ -0,0 +1,43 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::Dimension;
+use super::{Canvas, SelectedObject};
+
+#[wasm_bindgen]
+impl Canvas {
+    /// Shows or hides the length dimension of every selected line.
+    pub fn toggle_length_dimensions(&mut self) -> Result<(), JsValue> {
+        let dimensions: Vec<(usize, Dimension)> = self.get_selected_lines()
+            .into_iter()
+            .map(|(polygon_id, line_id)| (polygon_id, Dimension::Length(line_id)))
+            .collect();
+        self.toggle_dimensions(dimensions)
+    }
+
+    /// Shows or hides the angle dimension of every selected vertex.
+    pub fn toggle_angle_dimensions(&mut self) -> Result<(), JsValue> {
+        let dimensions: Vec<(usize, Dimension)> = self.selection
+            .iter()
+            .filter_map(|(polygon_id, object)| match object {
+                SelectedObject::Point(point_id) => Some((*polygon_id, Dimension::Angle(*point_id))),
+                _ => None
+            })
+            .collect();
+        self.toggle_dimensions(dimensions)
+    }
+}
+
+impl Canvas {
+    fn toggle_dimensions(&mut self, dimensions: Vec<(usize, Dimension)>) -> Result<(), JsValue> {
+        if dimensions.is_empty() {
+            return Ok(());
+        }
+        self.save_history();
+        for (polygon_id, dimension) in dimensions {
+            self.get_polygon_mut(polygon_id)?.toggle_dimension(dimension)?;
+        }
+        self.draw();
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
 
-use crate::{data_models::Point, draw::{clear_canvas, BASIC_COLOR}};
-use super::Canvas;
+use crate::{data_models::Point, draw::{clear_canvas, draw_grid, draw_snap_indicator, draw_transform_handles, draw_pivot, highlight_line, highlight_point, BASIC_COLOR}};
+use super::{Canvas, State, PressedObject, SelectedObject};
+
+const MIN_GRID_SPACING: f64 = 4.0;
 
 #[wasm_bindgen]
 impl Canvas {
@@ -10,10 +12,32 @@ impl Canvas {
         let relation_number = 0;
         let mut relation_map: HashMap<u32, u32> = HashMap::new();
         clear_canvas(&self.context);
+        self.apply_viewport();
 
-        self.polygons
-            .iter()
-            .for_each(|polygon| polygon.draw(&self.context, relation_number, &mut relation_map));
+        if self.snap.grid && self.snap.grid_size*self.viewport.scale >= MIN_GRID_SPACING {
+            let (a, b) = self.get_visible_area();
+            draw_grid(&self.context, self.snap.grid_size, a, b);
+        }
+
+        let meshes = if self.show_triangulation {Some(self.get_meshes())} else {None};
+        for i in (0..self.polygons.len()).filter(|i| self.is_visible(*i)) {
+            let wireframe = meshes.as_ref().map(|meshes| &meshes[i]);
+            self.polygons[i].draw(&self.context, relation_number, &mut relation_map, wireframe, &self.units);
+        }
+
+        self.draw_selection();
+
+        match self.state {
+            State::Edit | State::Moving(_) => {
+                (0..self.polygons.len())
+                    .filter(|i| self.is_editable(*i))
+                    .for_each(|i| draw_transform_handles(&self.context, self.polygons[i].center));
+                if let Some(pivot) = self.pivot {
+                    draw_pivot(&self.context, pivot);
+                }
+            },
+            _ => {}
+        }
 
         self.current_points
             .first()
@@ -36,13 +60,52 @@ impl Canvas {
             });
             self.context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
             self.context.stroke();
+
+        match (&self.state, self.shape_anchor) {
+            (State::Create, Some(anchor)) => self.draw_shape_preview(anchor),
+            (State::Create, None) => self.draw_sketch(),
+            _ => {}
+        }
+
+        match (&self.state, self.snap_target) {
+            (State::Create, Some((p, target))) | (State::Moving((_, PressedObject::Point(_))), Some((p, target))) => draw_snap_indicator(&self.context, p, target),
+            _ => {}
+        }
+    }
+
+    pub fn draw_selection(&self){
+        self.selection
+            .iter()
+            .for_each(|(polygon_id, object)| {
+                let polygon = &self.polygons[*polygon_id];
+                match object {
+                    SelectedObject::Polygon => {
+                        polygon.lines
+                            .iter()
+                            .filter_map(|line| polygon.get_line_cords(line).ok())
+                            .for_each(|(p1, p2)| highlight_line(&self.context, p1, p2));
+                        highlight_point(&self.context, polygon.center);
+                    },
+                    SelectedObject::Line(line_id) => {
+                        if let Ok((p1, p2)) = polygon.get_line_reference_inmut(*line_id).and_then(|line| polygon.get_line_cords(line)) {
+                            highlight_line(&self.context, p1, p2);
+                        }
+                    },
+                    SelectedObject::Point(point_id) => {
+                        if let Ok(point) = polygon.get_point_by_id(*point_id) {
+                            highlight_point(&self.context, point);
+                        }
+                    }
+                }
+            });
     }
 
     pub fn draw_bresenham(&self){
         clear_canvas(&self.context);
+        self.apply_viewport();
 
-        self.polygons
-            .iter()
-            .for_each(|polygon| polygon.draw_bresenham(&self.context));
+        (0..self.polygons.len())
+            .filter(|i| self.is_visible(*i))
+            .for_each(|i| self.polygons[i].draw_bresenham(&self.context));
     }
 }
\ No newline at end of file
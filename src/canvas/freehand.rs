@@ -0,0 +1,96 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::PointCords;
+use crate::draw::BASIC_COLOR;
+use crate::fitting::fit_sketch;
+use crate::utils::get_line_length;
+use super::Canvas;
+
+const DEFAULT_TOLERANCE: f64 = 4.0;
+const SAMPLE_DISTANCE: f64 = 2.0;
+
+pub struct FreehandSettings {
+    pub tolerance: f64,
+    pub fit_curves: bool
+}
+
+impl FreehandSettings {
+    pub fn new() -> FreehandSettings {
+        FreehandSettings { tolerance: DEFAULT_TOLERANCE, fit_curves: true }
+    }
+}
+
+impl Default for FreehandSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn set_freehand(&mut self, tolerance: f64, fit_curves: bool) {
+        self.freehand = FreehandSettings {
+            tolerance: if tolerance > 0.0 {tolerance} else {DEFAULT_TOLERANCE},
+            fit_curves
+        };
+    }
+
+    /// A stroke released outside the board never gets a click, so it is finished by any mouse up.
+    pub fn end_sketch(&mut self) -> bool {
+        if self.sketch.is_empty() {
+            return false;
+        }
+        self.finish_sketch()
+    }
+}
+
+impl Canvas {
+    pub fn start_sketch(&mut self, p: PointCords) {
+        self.sketch = vec![p];
+    }
+
+    /// Samples closer than a few screen pixels to the previous one are skipped.
+    pub fn add_sketch_sample(&mut self, p: PointCords) {
+        match self.sketch.last() {
+            Some(last) if get_line_length(*last, p) >= SAMPLE_DISTANCE/self.viewport.scale => self.sketch.push(p),
+            _ => {}
+        }
+    }
+
+    /// Closes the stroke into a polygon, tolerances are given in screen pixels.
+    pub fn finish_sketch(&mut self) -> bool {
+        let samples = std::mem::take(&mut self.sketch);
+        let tolerance = self.freehand.tolerance/self.viewport.scale;
+        let result = fit_sketch(&samples, tolerance, self.freehand.fit_curves, &mut self.ids, self.centroid_mode);
+        match result {
+            Ok(polygon) => {
+                self.save_history();
+                self.push_polygon(polygon, self.active_layer);
+                if self.shared_topology {
+                    self.weld_shared();
+                }
+                self.draw();
+                true
+            },
+            Err(_) => {
+                self.draw();
+                false
+            }
+        }
+    }
+
+    pub fn draw_sketch(&self) {
+        let first = match self.sketch.first() {
+            Some(first) => first,
+            None => return
+        };
+        self.context.begin_path();
+        self.context.move_to(first.0, first.1);
+        self.sketch
+            .iter()
+            .for_each(|p| self.context.line_to(p.0, p.1));
+        self.context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+        self.context.stroke();
+    }
+}
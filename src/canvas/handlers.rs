@@ -3,96 +3,257 @@ use crate::polygon::*;
 use crate::data_models::*;
 use crate::utils::*;
 use crate::draw::*;
-use super::{Canvas, State, PressedObject};
+use crate::transform::*;
+use super::{Canvas, State, PressedObject, SelectedObject};
+
+const MIN_SCALE_DISTANCE: f64 = 1.0;
 
 
 #[wasm_bindgen]
 impl Canvas{
     pub fn set_create_state(&mut self){
         self.state = State::Create;
+        self.selection.clear();
     }
 
     pub fn set_edit_state(&mut self){
         self.state = State::Edit;
+        self.selection.clear();
         self.clear_current_points();
         self.draw();
     }
 
     pub fn set_rules_state(&mut self){
         self.clear_current_points();
+        self.selection.clear();
         self.state = State::Rules(None);
         self.draw();
     }
 
-    pub fn make_bezier(&mut self){
-        match self.state {
-            State::Rules(Some((polygon_id, line_id))) =>{
-                let line_cords = self.get_line_by_id(line_id);
-                let bezier_cords = get_bezier_cords(line_cords);
-                self.polygons[polygon_id].set_bezier(line_id, Some(bezier_cords));
-                self.state = State::Rules(None);
-                self.draw();
-            },
-            _ => {}
+    pub fn remove_last_point(&mut self){
+        self.current_points.pop();
+        self.snap_target = None;
+        self.draw();
+    }
+
+    pub fn cancel_polygon(&mut self){
+        self.clear_current_points();
+        self.snap_target = None;
+        self.draw();
+    }
+
+    pub fn finish_path(&mut self) -> bool{
+        let has_zero_length_line = self.current_points
+            .windows(2)
+            .any(|pair| get_line_length(PointCords(pair[0].x, pair[0].y), PointCords(pair[1].x, pair[1].y)) == 0.0);
+        if self.current_points.len() < 2 || has_zero_length_line {
+            return false;
+        }
+        self.save_history();
+        let mut points = self.clear_current_points();
+        points.reverse();
+        let lines = calculate_path_lines(points.iter().collect(), &mut self.ids);
+        self.push_polygon(Polygon::new(points, lines, self.centroid_mode), self.active_layer);
+        if self.shared_topology {
+            self.weld_shared();
         }
+        self.snap_target = None;
+        self.draw();
+        true
     }
 
-    pub fn remove_relations(&mut self){
-        match self.state {
-            State::Rules(Some((polygon_id, line_id))) => {
-                let relation = self.polygons[polygon_id].get_line_relation(line_id);
-                self.polygons[polygon_id].set_relation(line_id, None);
-                match relation {
-                    Some(id) => {
-                        for j in 0..self.polygons.len() {
-                            self.polygons[j].set_relation(id, None);
-                        }
-                    },
-                    None => {}
+    pub fn toggle_closed(&mut self) -> Result<bool, JsValue>{
+        let (polygon_id, line_id) = match self.get_selected_lines().first() {
+            Some(selected) => *selected,
+            None => return Ok(false)
+        };
+        let mut polygon = self.get_polygon(polygon_id)?.clone();
+        if polygon.is_closed() {
+            let relation_updates = polygon.open_at(line_id)?;
+            self.save_history();
+            self.polygons[polygon_id] = polygon;
+            self.apply_relation_updates(relation_updates);
+        } else {
+            polygon.close(&mut self.ids)?;
+            self.save_history();
+            self.polygons[polygon_id] = polygon;
+        }
+        self.selection.clear();
+        self.state = State::Rules(None);
+        self.draw();
+        Ok(true)
+    }
+
+    pub fn close_polygon(&mut self) -> bool{
+        let outline: Vec<PointCords> = self.current_points
+            .iter()
+            .map(|point| PointCords(point.x, point.y))
+            .collect();
+        if !is_simple_ring(&outline) {
+            self.draw();
+            match (outline.first(), outline.last()) {
+                (Some(first), Some(last)) if outline.len() >= 2 => highlight_line(&self.context, *last, *first),
+                _ => {}
+            }
+            return false;
+        }
+        self.save_history();
+        let points = self.clear_current_points();
+        let lines = calcualate_new_lines(points.iter().collect(), &mut self.ids);
+        let new_polygon = Polygon::new(points, lines, self.centroid_mode);
+        self.push_polygon(new_polygon, self.active_layer);
+        if self.shared_topology {
+            self.weld_shared();
+        }
+        self.snap_target = None;
+        clear_canvas(&self.context);
+        self.draw();
+        true
+    }
+
+    pub fn set_centroid_mode(&mut self, vertex_mean: bool){
+        self.centroid_mode = if vertex_mean {CentroidMode::VertexMean} else {CentroidMode::Area};
+        for i in 0..self.polygons.len() {
+            self.polygons[i].centroid_mode = self.centroid_mode;
+            self.polygons[i].update_center();
+        }
+        self.draw();
+    }
+
+    pub fn make_bezier(&mut self) -> Result<(), JsValue>{
+        let edited = self.edit_selected_lines(|canvas, polygon_id, line_id| {
+            let line_cords = canvas.get_line_by_id(line_id)?;
+            let bezier_cords = get_bezier_cords(line_cords);
+            canvas.get_polygon_mut(polygon_id)?.set_bezier(line_id, Some(bezier_cords));
+            canvas.commit_shared()
+        })?;
+        if edited {
+            self.selection.clear();
+            self.state = State::Rules(None);
+            self.draw();
+        }
+        Ok(())
+    }
+
+    pub fn split_selected_lines_at(&mut self, t: f64) -> Result<(), JsValue>{
+        let edited = self.edit_selected_lines(|canvas, polygon_id, line_id| {
+            canvas.split_shared_line(polygon_id, line_id, |polygon, ids| polygon.split_line(line_id, t, ids).map(|point_id| vec![point_id]))
+        })?;
+        if edited {
+            self.selection.clear();
+            self.state = State::Rules(None);
+            self.draw();
+        }
+        Ok(())
+    }
+
+    pub fn split_selected_lines(&mut self, parts: u32) -> Result<(), JsValue>{
+        if parts < 2 {
+            return Ok(());
+        }
+        let edited = self.edit_selected_lines(|canvas, polygon_id, line_id| {
+            canvas.split_shared_line(polygon_id, line_id, |polygon, ids| polygon.split_line_into(line_id, parts, ids))
+        })?;
+        if edited {
+            self.selection.clear();
+            self.state = State::Rules(None);
+            self.draw();
+        }
+        Ok(())
+    }
+
+    pub fn remove_relations(&mut self) -> Result<(), JsValue>{
+        let edited = self.edit_selected_lines(|canvas, polygon_id, line_id| {
+            let relation = canvas.get_polygon(polygon_id)?.get_line_relation(line_id)?;
+            canvas.polygons[polygon_id].set_relation(line_id, None);
+            if let Some(id) = relation {
+                for j in 0..canvas.polygons.len() {
+                    canvas.polygons[j].set_relation(id, None);
                 }
-                self.draw();
-            },
-            _ => {}
+            }
+            canvas.commit_shared()
+        })?;
+        if edited {
+            self.draw();
         }
+        Ok(())
     }
 
-    pub fn set_line_length(&mut self){
-        match self.state {
-            State::Rules(Some((polygon_id, line_id))) => {
-                let mut line = self.polygons[polygon_id].get_line_reference(line_id);
-                let new_length = self.length_selector.value_as_number();
-                let extention = (new_length - line.length)/2.0;
-                line.length = new_length;
-                let (p1_id, p2_id) = line.points;
-                self.correct_line_mid(extention, line_id, polygon_id);
-                self.correct_line_length(p2_id, polygon_id, true);
-                self.correct_line_length(p1_id, polygon_id, false);
-                self.reset_visited();
-                self.recalculate();
-                self.draw();
-            },
-            _ => {}
+    pub fn set_line_length(&mut self) -> Result<(), JsValue>{
+        let new_length = self.units.to_pixels(self.length_selector.value_as_number());
+        let edited = self.edit_selected_lines(|canvas, polygon_id, line_id| {
+            let line = canvas.get_polygon_mut(polygon_id)?.get_line_reference(line_id)?;
+            let extention = (new_length - line.length)/2.0;
+            line.length = new_length;
+            let (p1_id, p2_id) = line.points;
+            let result = canvas.correct_line_mid(extention, line_id, polygon_id)
+                .and_then(|_| canvas.correct_line_length(p2_id, polygon_id, true))
+                .and_then(|_| canvas.correct_line_length(p1_id, polygon_id, false));
+            canvas.reset_visited();
+            let result = result.and_then(|_| canvas.commit_shared());
+            canvas.recalculate();
+            result
+        })?;
+        if edited {
+            self.draw();
         }
+        Ok(())
     }
 
-    pub fn set_const_state(&mut self){
-        match self.state {
-            State::Rules(Some((polygon_id, line_id))) => {
-                let mut line = self.polygons[polygon_id].get_line_reference(line_id);
-                line.is_const = self.is_const.checked();
-            },
-            _ => {}
+    pub fn set_const_state(&mut self) -> Result<(), JsValue>{
+        let is_const = self.is_const.checked();
+        let lines: Vec<(usize, u32)> = self.get_selected_lines()
+            .into_iter()
+            .filter(|(polygon_id, line_id)| self.get_polygon(*polygon_id)
+                .and_then(|polygon| polygon.get_line_reference_inmut(*line_id))
+                .map_or(true, |line| line.is_const != is_const))
+            .collect();
+        if lines.is_empty() {
+            return Ok(());
         }
+        self.save_history();
+        // shared copies are committed once for all lines, a conflict rolls every line back
+        let result = lines
+            .into_iter()
+            .try_for_each(|(polygon_id, line_id)| {
+                self.get_polygon_mut(polygon_id)?.get_line_reference(line_id)?.is_const = is_const;
+                Ok(())
+            })
+            .and_then(|_| self.commit_shared());
+        self.revert_failed_edit(result)?;
+        self.draw();
+        Ok(())
     }
 
-    pub fn on_down_click(&mut self, x: f64, y: f64){
+    pub fn on_down_click(&mut self, x: f64, y: f64, shift: bool){
+        let PointCords(x, y) = self.viewport.to_world(PointCords(x, y));
         match &self.state {
+            State::Create if self.shape_tool == ShapeTool::Freehand => self.start_sketch(PointCords(x, y)),
             State::Edit => {
                 self.draw();
-                for i in 0..self.polygons.len() {
-                    match self.polygons[i].check_hover(x, y){
-                        Some(pressed_object) => {self.state = State::Moving((i, pressed_object)); break;},
-                        _ => {}
+                let hovered = self.get_hovered_handle(x, y).or(self.get_hovered_object(x, y));
+                match hovered {
+                    Some((i, pressed_object)) if shift => {
+                        if let Some(object) = Canvas::get_selected_object(&pressed_object) {
+                            self.toggle_selection(i, object);
+                        }
+                        self.draw();
+                    },
+                    Some((i, pressed_object)) if self.is_selected(i, &pressed_object) => {
+                        self.prepare_history();
+                        self.state = State::MovingSelection(PointCords(x, y));
+                    },
+                    Some(hovered) => {
+                        self.prepare_history();
+                        self.selection.clear();
+                        self.drop_origin = match hovered {
+                            (i, PressedObject::Point(point_id)) => self.get_drop_target(i, point_id).map(|(_, target)| target.id),
+                            _ => None
+                        };
+                        self.state = State::Moving(hovered);
+                    },
+                    None => {
+                        self.state = State::Selecting((PointCords(x, y), shift));
                     }
                 }
             },
@@ -100,27 +261,59 @@ impl Canvas{
         }
     }
 
-    pub fn on_left_click(&mut self, x: f64, y: f64){
+    pub fn on_left_click(&mut self, x: f64, y: f64, shift: bool) -> Result<(), JsValue>{
+        let PointCords(x, y) = self.viewport.to_world(PointCords(x, y));
         match self.state {
+            State::Create if self.shape_tool == ShapeTool::Freehand => {
+                self.finish_sketch();
+            },
+            State::Create if self.shape_tool != ShapeTool::Polygon => {
+                let p = self.snap_point(x, y, None);
+                self.add_shape_point(p);
+            },
             State::Create => {
-                self.current_points.push(Point{x,y, id:self.current_id});
-                self.current_id = self.current_id + 1;
+                let PointCords(x, y) = self.snap_point(x, y, None);
+                let closes_ring = self.current_points.len() >= 3 && self.current_points
+                    .first()
+                    .is_some_and(|first| check_point_hover(PointCords(first.x, first.y), PointCords(x, y), self.viewport.scale));
+                if closes_ring {
+                    self.close_polygon();
+                    return Ok(());
+                }
+                self.current_points.push(Point{x,y, id: self.ids.allocate()});
                 clear_canvas(&self.context);
                 self.draw();
             },
-            State::Moving(_) => {self.state = State::Edit},
-            State::Rules(_) => {
+            State::Moving((polygon_id, PressedObject::Point(point_id))) => {
+                self.state = State::Edit;
+                let result = self.merge_dropped_point(polygon_id, point_id);
+                self.draw();
+                result?;
+            },
+            State::Moving(_) | State::MovingSelection(_) => {self.state = State::Edit},
+            State::Selecting((start, add)) => {
+                self.select_in_rect(start, PointCords(x, y), add);
+                self.state = State::Edit;
+                self.draw();
+            },
+            State::Rules(selected) => {
                 self.draw();
                 for i in 0..self.polygons.len() {
-                    match self.polygons[i].check_hover(x, y){
+                    match self.check_polygon_hover(i, x, y){
+                        Some(PressedObject::Line(id,_)) if shift && selected.is_some() => {
+                            if selected != Some((i, id)) {
+                                self.toggle_selection(i, SelectedObject::Line(id));
+                            }
+                            self.draw();
+                            break;
+                        },
                         Some(PressedObject::Line(id,_)) => {
+                            self.selection.clear();
                             self.state = State::Rules(Some((i, id)));
-                            let (x_id,y_id) = self.polygons[i].get_line_by_id(id);
-                            let x = self.polygons[i].get_point_by_id(x_id);
-                            let y = self.polygons[i].get_point_by_id(y_id);
+                            let line = self.polygons[i].get_line_reference_inmut(id)?;
+                            let (x, y) = self.polygons[i].get_line_cords(line)?;
                             highlight_line(&self.context, x, y);
-                            let line = self.polygons[i].get_line_reference(id);
-                            self.length_selector.set_value(format!("{:.2}", line.length).as_str());
+                            self.length_selector.set_value(format!("{:.2}", self.units.to_units(line.length)).as_str());
                             self.is_const.set_checked(line.is_const);
                             break;
                         },
@@ -130,242 +323,120 @@ impl Canvas{
             },
             _ => {},
         }
+        Ok(())
     }
 
-    pub fn on_move_mouse(&mut self, x: f64, y: f64){
+    pub fn on_move_mouse(&mut self, x: f64, y: f64) -> Result<(), JsValue>{
+        let PointCords(x, y) = self.viewport.to_world(PointCords(x, y));
+        self.cursor = PointCords(x, y);
+        match self.state {
+            State::Moving(_) | State::MovingSelection(_) => self.commit_prepared_history(),
+            _ => {}
+        }
         match &self.state {
+            State::Create if self.shape_tool == ShapeTool::Freehand => {
+                self.add_sketch_sample(PointCords(x, y));
+                self.draw();
+            },
+            State::Create if self.shape_tool != ShapeTool::Polygon => {
+                self.snap_point(x, y, None);
+                self.draw();
+            },
             State::Create => {
+                let PointCords(x, y) = self.snap_point(x, y, None);
                 self.current_points.push(Point{x,y, id: 0});
                 self.draw();
                 self.current_points.pop();
             },
-            State::Moving((id, pressed_object)) => {
-                match pressed_object {
-                    PressedObject::Center => {
-                        let mut polygon = &mut self.polygons[*id];
-                        
-                        let difference_vec = (x-polygon.center.0, y-polygon.center.1);
-
-                        polygon.points
-                            .iter_mut()
-                            .for_each(|point| {
-                                point.x = point.x + difference_vec.0;
-                                point.y = point.y + difference_vec.1;
-                            });
-
-                        polygon.lines
-                            .iter_mut()
-                            .for_each(|line| {
-                                match line.bezier {
-                                    Some((p1,p2)) => {
-                                        line.bezier = Some((
-                                                PointCords(p1.0 + difference_vec.0, p1.1 + difference_vec.1),
-                                                PointCords(p2.0 + difference_vec.0, p2.1 + difference_vec.1),
-                                                ))
-                                    },
-                                    None => {}
-                                };
-                            });
-
-                        polygon.center.0 = x;
-                        polygon.center.1 = y;
-
-                        self.polygons[*id].center = get_centroid(&self.polygons[*id].points);
-                        self.draw();
-                    },
-                    PressedObject::Line(line_id, offset) => {
-                        let (p1_id, p2_id)= self.polygons[*id].get_line_by_id(*line_id);
-                        let p_id = *id;
-                        let p1_val = self.polygons[*id].get_point_by_id(p1_id);
-
-                        let last_click_point = get_click_point(p1_val, *offset);
-
-                        let difference_vec = (x-last_click_point.0, y-last_click_point.1);
-
-                        self.polygons[*id].modify_point_coordinates(p1_id, difference_vec);
-                        self.polygons[*id].modify_point_coordinates(p2_id, difference_vec);
-                        self.correct_line_length(p1_id, p_id, false);
-                        self.correct_line_length(p2_id, p_id, true);
-                        self.reset_visited();
-                        self.recalculate();
-                        self.draw();
-                    },
-                    PressedObject::Point(point_id) => {
-                        let mut point = self.polygons[*id].get_point_reference(*point_id);
-                        let px_id = *point_id;
-                        let p_id = *id;
-                        point.x = x;
-                        point.y = y;
-                        self.correct_line_length(px_id, p_id, false);
-                        self.correct_line_length(px_id, p_id, true);
-                        self.reset_visited();
-                        self.recalculate();
-                        self.draw();
-                    },
-                    PressedObject::BesierLine(line_id, point) => {
-                        let mut line = self.polygons[*id].get_line_reference(*line_id);
-                        let old_bezier = line.bezier.unwrap();
-                        if *point == 1 {
-                            line.bezier = Some((PointCords(x,y), old_bezier.1))
-                        } else {
-                            line.bezier = Some((old_bezier.0, PointCords(x,y)))
-                        }
-                        self.draw();
-                    }
-                }
+            State::Selecting((start, _)) => {
+                let start = *start;
+                self.draw();
+                draw_selection_rect(&self.context, start, PointCords(x, y));
+            },
+            State::Moving(_) | State::MovingSelection(_) => {
+                // a failed step cancels the whole drag like Escape does
+                let result = self.drag_to(x, y);
+                self.revert_failed_edit(result)?;
             },
             State::Rules(Some((polygon_id, line_id))) => {
                 self.draw();
                 for i in 0..self.polygons.len() {
-                    match self.polygons[i].check_hover(x,y){
-                        Some(PressedObject::Line(id, _)) =>{
-                            let (p1_id, p2_id) = self.polygons[i].get_line_by_id(id);
-                            let p1 = self.polygons[i].get_point_by_id(p1_id);
-                            let p2 = self.polygons[i].get_point_by_id(p2_id);
-                            highlight_line(&self.context, p1, p2);
-                            break;
-                        },
-                        _ => {}
+                    if let Some(PressedObject::Line(id, _)) = self.check_polygon_hover(i, x, y) {
+                        let (p1, p2) = self.polygons[i].get_line_cords(self.polygons[i].get_line_reference_inmut(id)?)?;
+                        highlight_line(&self.context, p1, p2);
+                        break;
                     }
                 }
 
-                let (x_id,y_id) = self.polygons[*polygon_id].get_line_by_id(*line_id);
-                let x = self.polygons[*polygon_id].get_point_by_id(x_id);
-                let y = self.polygons[*polygon_id].get_point_by_id(y_id);
+                let polygon = self.get_polygon(*polygon_id)?;
+                let (x, y) = polygon.get_line_cords(polygon.get_line_reference_inmut(*line_id)?)?;
                 highlight_line(&self.context, x, y);
             },
             _ => {
                 self.draw();
-                for i in 0..self.polygons.len() {
-                    match self.polygons[i].check_hover(x,y){
-                        Some(PressedObject::Center) => {highlight_point(&self.context, self.polygons[i].center); break;},
-                        Some(PressedObject::Line(id, _)) =>{
-                            let (p1_id, p2_id) = self.polygons[i].get_line_by_id(id);
-                            let p1 = self.polygons[i].get_point_by_id(p1_id);
-                            let p2 = self.polygons[i].get_point_by_id(p2_id);
-                            highlight_line(&self.context, p1, p2);
-                            break;
-                        },
-                        Some(PressedObject::Point(id)) => {
-                            let hovered_point_cords = self.polygons[i].get_point_by_id(id);
-                            highlight_point(&self.context, hovered_point_cords);
-                            break;
-                        },
-                        Some(PressedObject::BesierLine(line_id, point)) => {
-                            if point == 1 {
-                                highlight_point(&self.context, self.polygons[i].get_line_reference(line_id).bezier.unwrap().0);
-                            } else {
-                                highlight_point(&self.context, self.polygons[i].get_line_reference(line_id).bezier.unwrap().1);
-                            }
+                let hovered_handle = match self.state {
+                    State::Edit => self.get_hovered_handle(x, y),
+                    _ => None
+                };
+                match hovered_handle.or(self.get_hovered_object(x, y)) {
+                    Some((i, PressedObject::RotationHandle(_))) => {highlight_point(&self.context, get_rotation_handle(self.polygons[i].center));},
+                    Some((i, PressedObject::ScaleHandle(_))) => {highlight_point(&self.context, get_scale_handle(self.polygons[i].center));},
+                    Some((i, PressedObject::Center)) | Some((i, PressedObject::Inside(_))) => {highlight_point(&self.context, self.polygons[i].center);},
+                    Some((i, PressedObject::Line(id, _))) =>{
+                        let (p1, p2) = self.polygons[i].get_line_cords(self.polygons[i].get_line_reference_inmut(id)?)?;
+                        highlight_line(&self.context, p1, p2);
+                    },
+                    Some((i, PressedObject::Point(id))) => {
+                        let hovered_point_cords = self.polygons[i].get_point_by_id(id)?;
+                        highlight_point(&self.context, hovered_point_cords);
+                    },
+                    Some((i, PressedObject::BesierLine(line_id, point))) => {
+                        match self.polygons[i].get_line_reference_inmut(line_id)?.bezier {
+                            Some((b1, _)) if point == 1 => highlight_point(&self.context, b1),
+                            Some((_, b2)) => highlight_point(&self.context, b2),
+                            None => {}
                         }
-                        None => {}
                     }
+                    None => {}
                 }
             },
         }
+        Ok(())
     }
 
-    pub fn on_right_click(&mut self, x: f64, y: f64){
+    pub fn on_right_click(&mut self, x: f64, y: f64) -> Result<(), JsValue>{
+        let PointCords(x, y) = self.viewport.to_world(PointCords(x, y));
         match self.state {
             State::Create => {
-                let points = self.clear_current_points();
-                let lines = calcualate_new_lines(points.iter().collect());
-                if points.len() >= 3 {
-                    let center = get_centroid(&points);
-                    let new_polygon = Polygon { lines, points, center};
-                    self.polygons.push(new_polygon);
-                }
-                clear_canvas(&self.context);
-                self.draw();
+                self.close_polygon();
             },
             State::Edit => {
                 self.draw();
                 let len = self.polygons.len();
                 for i in 0..len {
-                    match self.polygons[i].check_hover(x, y){
+                    match self.check_polygon_hover(i, x, y){
                         Some(PressedObject::Line(id,_)) => {
-                            let (p1_id, p2_id) = self.polygons[i].get_line_by_id(id);
-                            let relation = self.polygons[i].get_line_relation(id);
-                            match relation {
-                                Some(id) => {
-                                    for j in 0..len {
-                                        self.polygons[j].set_relation(id, None);
-                                    }
-                                },
-                                None => {}
+                            // a click in the hover margin beyond an end of the line does not split it
+                            if let Some(t) = self.polygons[i].get_split_parameter(id, PointCords(x, y))? {
+                                self.save_history();
+                                let result = self.split_shared_line(i, id, |polygon, ids| polygon.split_line(id, t, ids).map(|point_id| vec![point_id]));
+                                self.revert_failed_edit(result)?;
                             }
-                            let p1 = self.polygons[i].get_point_by_id(p1_id);
-                            let p2 = self.polygons[i].get_point_by_id(p2_id);
-                            let mut j = 0;
-                            while j < self.polygons[i].points.len() {
-                                if self.polygons[i].points[j].id == p2_id {
-                                    break;
-                                }
-                                j = j + 1;
-                            }
-                            let new_point_pos = calculate_middle_point(p1, p2);
-                            self.polygons[i].points.insert(j, Point { x: new_point_pos.0, y: new_point_pos.1, id: self.current_id});
-
-                            j = 0;
-                            while j < self.polygons[i].lines.len() {
-                                if self.polygons[i].lines[j].id == id {
-                                    break;
-                                }
-                                j = j + 1;
-                            }
-                            let (l1, l2) = get_new_split_lines(&self.polygons[i], p1_id, p2_id, self.current_id);
-                            self.polygons[i].lines[j] = l1;
-                            self.polygons[i].lines.insert(j+1, l2);
-                            self.polygons[i].center = get_centroid(&self.polygons[i].points);
-                            self.current_id = self.current_id + 1;
                             self.draw();
                             break;
                         },
                         Some(PressedObject::Point(id)) => {
-                            if self.polygons[i].points.len() <= 3 {
-                                for k in 0..self.polygons[i].lines.len() {
-                                    match self.polygons[i].lines[k].relation {
-                                        Some(line_id) => {
-                                            for j in 0..len {
-                                                self.polygons[j].set_relation(line_id, None);
-                                            }
-                                        },
-                                        None => {}
-                                    }
-                                }
-                                self.polygons.remove(i);
-                                self.draw();
-                                break;
-                            }
-                            for j in  0..self.polygons[i].lines.len() {
-                                if self.polygons[i].lines[j].points.1 == id || self.polygons[i].lines[j].points.0 == id {
-                                    let rel = self.polygons[i].lines[j].relation;
-                                    match rel {
-                                        Some(line_id) => {
-                                            for h in 0..len {
-                                                self.polygons[h].set_relation(line_id, None);
-                                            }
-                                        },
-                                        None => {}
-                                    }
-                                }
-                            }
-                            self.polygons[i].remove_point_of_id(id, true);
+                            self.save_history();
+                            let result = self.remove_shared_point(id);
+                            self.revert_failed_edit(result)?;
+                            self.selection.clear();
                             self.draw();
                             break;
                         }
                         Some(PressedObject::Center) => {
-                            for k in  0..self.polygons[i].lines.len() {
-                                match self.polygons[i].lines[k].relation {
-                                    Some(line_id) => {
-                                        for j in 0..len {
-                                            self.polygons[j].set_relation(line_id, None);
-                                        }
-                                    },
-                                    None => {}
-                                }
-                            }
-                            self.polygons.remove(i);
+                            self.save_history();
+                            let result = self.remove_polygon(i);
+                            self.revert_failed_edit(result)?;
                             self.draw();
                             break;
                         },
@@ -377,22 +448,21 @@ impl Canvas{
             State::Rules(Some((old_polygon_id, old_line_id))) => {
                 self.draw();
                 for i in 0..self.polygons.len() {
-                    match self.polygons[i].check_hover(x, y){
-                        Some(PressedObject::Line(new_line_id,_)) => {
-                            if new_line_id == old_line_id {return;}
-                            let new_relation = self.polygons[i].get_line_relation(new_line_id);
-                            let old_relation = self.polygons[old_polygon_id].get_line_relation(old_line_id);
-                            match (new_relation, old_relation) {
-                                (None, None) => {
-                                    self.polygons[old_polygon_id].set_relation(old_line_id, Some(new_line_id));
-                                    self.polygons[i].set_relation(new_line_id, Some(old_line_id));
-                                    self.enforce_relation(old_line_id, new_line_id);
-                                },
-                                _ => {}
-                            };
-                            break;
-                        },
-                        _ => {}
+                    if let Some(PressedObject::Line(new_line_id,_)) = self.check_polygon_hover(i, x, y) {
+                        if new_line_id == old_line_id {return Ok(());}
+                        let new_relation = self.polygons[i].get_line_relation(new_line_id)?;
+                        let old_relation = self.get_polygon(old_polygon_id)?.get_line_relation(old_line_id)?;
+                        if let (None, None) = (new_relation, old_relation) {
+                            self.save_history();
+                            self.polygons[old_polygon_id].set_relation(old_line_id, Some(new_line_id));
+                            self.polygons[i].set_relation(new_line_id, Some(old_line_id));
+                            let result = self.enforce_relation(old_line_id, new_line_id);
+                            self.reset_visited();
+                            let result = result.and_then(|_| self.commit_shared());
+                            self.recalculate();
+                            self.revert_failed_edit(result)?;
+                        }
+                        break;
                     }
                 }
                 self.recalculate();
@@ -400,5 +470,130 @@ impl Canvas{
             }
             _ => {}
         }
+        Ok(())
+    }
+}
+impl Canvas {
+    /// Applies the edit to every selected line as one undo step, a failure on any line rolls all of them back.
+    /// Returns false when no line is selected.
+    fn edit_selected_lines<F: FnMut(&mut Canvas, usize, u32) -> crate::error::Result<()>>(&mut self, mut edit: F) -> crate::error::Result<bool> {
+        let lines = self.get_selected_lines();
+        if lines.is_empty() {
+            return Ok(false);
+        }
+        self.save_history();
+        let result = lines
+            .into_iter()
+            .try_for_each(|(polygon_id, line_id)| edit(self, polygon_id, line_id));
+        self.revert_failed_edit(result)?;
+        Ok(true)
+    }
+
+    /// Moves whatever is being dragged to the cursor, the caller rolls a failed step back.
+    fn drag_to(&mut self, x: f64, y: f64) -> crate::error::Result<()> {
+        match &self.state {
+            State::MovingSelection(last) => {
+                let difference_vec = (x - last.0, y - last.1);
+                let result = self.transform_selection(&Transform::translation(difference_vec.0, difference_vec.1));
+                self.state = State::MovingSelection(PointCords(x, y));
+                self.draw();
+                result
+            },
+            State::Moving((id, pressed_object)) => {
+                match pressed_object {
+                    PressedObject::Center => {
+                        let polygon = self.get_polygon_mut(*id)?;
+                        polygon.translate(x - polygon.center.0, y - polygon.center.1);
+                        polygon.update_center();
+                        let result = self.commit_shared();
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::Inside(offset) => {
+                        let offset = *offset;
+                        let polygon = self.get_polygon_mut(*id)?;
+                        polygon.translate(x - offset.0 - polygon.center.0, y - offset.1 - polygon.center.1);
+                        polygon.update_center();
+                        let result = self.commit_shared();
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::RotationHandle(last_angle) => {
+                        let p_id = *id;
+                        let pivot = self.get_pivot(p_id);
+                        let angle = (y - pivot.1).atan2(x - pivot.0);
+                        self.polygons[p_id].apply_transform(&Transform::rotation(angle - *last_angle, pivot));
+                        self.state = State::Moving((p_id, PressedObject::RotationHandle(angle)));
+                        let result = self.commit_shared();
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::ScaleHandle(last_distance) => {
+                        let p_id = *id;
+                        let pivot = self.get_pivot(p_id);
+                        let distance = get_line_length(pivot, PointCords(x, y));
+                        if *last_distance > MIN_SCALE_DISTANCE && distance > MIN_SCALE_DISTANCE {
+                            let factor = distance/last_distance;
+                            self.polygons[p_id].apply_transform(&Transform::scale(factor, factor, pivot));
+                            self.state = State::Moving((p_id, PressedObject::ScaleHandle(distance)));
+                        }
+                        let result = self.commit_shared();
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::Line(line_id, offset) => {
+                        let (line_id, offset, p_id) = (*line_id, *offset, *id);
+                        let (p1_id, p2_id)= self.get_polygon(p_id)?.get_line_by_id(line_id)?;
+                        let p1_val = self.polygons[p_id].get_point_by_id(p1_id)?;
+
+                        let last_click_point = get_click_point(p1_val, offset);
+
+                        let difference_vec = (x-last_click_point.0, y-last_click_point.1);
+
+                        self.polygons[p_id].modify_point_coordinates(p1_id, difference_vec)?;
+                        self.polygons[p_id].modify_point_coordinates(p2_id, difference_vec)?;
+                        let result = self.correct_line_length(p1_id, p_id, false)
+                            .and_then(|_| self.correct_line_length(p2_id, p_id, true));
+                        self.reset_visited();
+                        let result = result.and_then(|_| self.commit_shared());
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::Point(point_id) => {
+                        let px_id = *point_id;
+                        let p_id = *id;
+                        let PointCords(x, y) = self.snap_point(x, y, Some((p_id, px_id)));
+                        let point = self.get_polygon_mut(p_id)?.get_point_reference(px_id)?;
+                        point.x = x;
+                        point.y = y;
+                        let result = self.correct_line_length(px_id, p_id, false)
+                            .and_then(|_| self.correct_line_length(px_id, p_id, true));
+                        self.reset_visited();
+                        let result = result.and_then(|_| self.commit_shared());
+                        self.recalculate();
+                        self.draw();
+                        result
+                    },
+                    PressedObject::BesierLine(line_id, point) => {
+                        let (p_id, line_id, point) = (*id, *line_id, *point);
+                        let line = self.get_polygon_mut(p_id)?.get_line_reference(line_id)?;
+                        match line.bezier {
+                            Some(old_bezier) if point == 1 => line.bezier = Some((PointCords(x,y), old_bezier.1)),
+                            Some(old_bezier) => line.bezier = Some((old_bezier.0, PointCords(x,y))),
+                            None => {}
+                        }
+                        let result = self.commit_shared();
+                        self.draw();
+                        result
+                    }
+                }
+            },
+            _ => Ok(())
+        }
     }
 }
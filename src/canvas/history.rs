@@ -0,0 +1,149 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::data_models::Layer;
+use crate::polygon::Polygon;
+use super::{Canvas, State};
+use super::topology::SharedRecords;
+
+const MAX_HISTORY: usize = 100;
+
+/// Shared records, the topology switch and the layers are restored together with the polygons referring to them.
+#[derive(Clone)]
+pub struct Snapshot {
+    polygons: Vec<Polygon>,
+    shared: SharedRecords,
+    shared_topology: bool,
+    layers: Vec<Layer>,
+    active_layer: u32
+}
+
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    pending: Option<Snapshot>
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History { undo: vec![], redo: vec![], pending: None }
+    }
+
+    fn push(&mut self, snapshot: Snapshot) {
+        self.undo.push(snapshot);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.pending = None;
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo.pop() {
+            Some(snapshot) => {
+                let current = self.restore_snapshot(snapshot);
+                self.history.redo.push(current);
+                self.restore_after_history();
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo.pop() {
+            Some(snapshot) => {
+                let current = self.restore_snapshot(snapshot);
+                self.history.undo.push(current);
+                self.restore_after_history();
+                true
+            },
+            None => false
+        }
+    }
+}
+
+impl Canvas {
+    pub fn save_history(&mut self) {
+        let snapshot = self.get_snapshot();
+        self.history.push(snapshot);
+    }
+
+    /// Remembers the scene at the start of a drag; it only becomes an undo step once something actually moves.
+    pub fn prepare_history(&mut self) {
+        self.history.pending = Some(self.get_snapshot());
+    }
+
+    pub fn commit_prepared_history(&mut self) {
+        if let Some(snapshot) = self.history.pending.take() {
+            self.history.push(snapshot);
+        }
+    }
+
+    pub fn discard_prepared_history(&mut self) -> bool {
+        self.history.pending.take().is_some()
+    }
+
+    /// Restores the last undo step without making the discarded state redoable, e.g. for a cancelled drag.
+    pub fn revert_last_history(&mut self) {
+        if let Some(snapshot) = self.history.undo.pop() {
+            self.restore_snapshot(snapshot);
+            self.restore_after_history();
+        }
+    }
+
+    /// Rolls an edit that failed halfway back to the undo step saved before it, the step is dropped as well.
+    pub fn revert_failed_edit<T, E>(&mut self, result: std::result::Result<T, E>) -> std::result::Result<T, E> {
+        if result.is_err() {
+            self.revert_last_history();
+        }
+        result
+    }
+
+    fn get_snapshot(&self) -> Snapshot {
+        Snapshot {
+            polygons: self.polygons.clone(),
+            shared: self.shared.clone(),
+            shared_topology: self.shared_topology,
+            layers: self.layers.clone(),
+            active_layer: self.active_layer
+        }
+    }
+
+    /// Returns the replaced state so it can be pushed to the opposite stack. Visibility and locking are view
+    /// settings, layers that still exist keep them.
+    fn restore_snapshot(&mut self, mut snapshot: Snapshot) -> Snapshot {
+        for layer in snapshot.layers.iter_mut() {
+            if let Some(current) = self.layers.iter().find(|current| current.id == layer.id) {
+                layer.visible = current.visible;
+                layer.locked = current.locked;
+            }
+        }
+        Snapshot {
+            polygons: std::mem::replace(&mut self.polygons, snapshot.polygons),
+            shared: std::mem::replace(&mut self.shared, snapshot.shared),
+            shared_topology: std::mem::replace(&mut self.shared_topology, snapshot.shared_topology),
+            layers: std::mem::replace(&mut self.layers, snapshot.layers),
+            active_layer: std::mem::replace(&mut self.active_layer, snapshot.active_layer)
+        }
+    }
+
+    fn restore_after_history(&mut self) {
+        self.normalize_layers();
+        self.selection.clear();
+        self.state = match self.state {
+            State::Create => State::Create,
+            State::Rules(_) => State::Rules(None),
+            _ => State::Edit
+        };
+        self.draw();
+    }
+}
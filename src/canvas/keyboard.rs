@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::error::Result;
+use crate::transform::Transform;
+use super::{Canvas, State, PressedObject};
+
+const NUDGE_STEP: f64 = 1.0;
+const NUDGE_LARGE_STEP: f64 = 10.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KeyCommand {
+    CreateMode,
+    EditMode,
+    RulesMode,
+    Cancel,
+    Delete,
+    NudgeLeft,
+    NudgeRight,
+    NudgeUp,
+    NudgeDown,
+    Undo,
+    Redo,
+    FinishPath
+}
+
+const COMMAND_NAMES: [(&str, KeyCommand); 12] = [
+    ("create_mode", KeyCommand::CreateMode),
+    ("edit_mode", KeyCommand::EditMode),
+    ("rules_mode", KeyCommand::RulesMode),
+    ("cancel", KeyCommand::Cancel),
+    ("delete", KeyCommand::Delete),
+    ("nudge_left", KeyCommand::NudgeLeft),
+    ("nudge_right", KeyCommand::NudgeRight),
+    ("nudge_up", KeyCommand::NudgeUp),
+    ("nudge_down", KeyCommand::NudgeDown),
+    ("undo", KeyCommand::Undo),
+    ("redo", KeyCommand::Redo),
+    ("finish_path", KeyCommand::FinishPath)
+];
+
+const DEFAULT_BINDINGS: [(&str, KeyCommand); 14] = [
+    ("c", KeyCommand::CreateMode),
+    ("e", KeyCommand::EditMode),
+    ("r", KeyCommand::RulesMode),
+    ("escape", KeyCommand::Cancel),
+    ("delete", KeyCommand::Delete),
+    ("backspace", KeyCommand::Delete),
+    ("arrowleft", KeyCommand::NudgeLeft),
+    ("arrowright", KeyCommand::NudgeRight),
+    ("arrowup", KeyCommand::NudgeUp),
+    ("arrowdown", KeyCommand::NudgeDown),
+    ("ctrl+z", KeyCommand::Undo),
+    ("ctrl+y", KeyCommand::Redo),
+    ("ctrl+shift+z", KeyCommand::Redo),
+    ("enter", KeyCommand::FinishPath)
+];
+
+impl KeyCommand {
+    fn from_name(name: &str) -> Option<KeyCommand> {
+        COMMAND_NAMES
+            .iter()
+            .find(|(command_name, _)| *command_name == name)
+            .map(|(_, command)| *command)
+    }
+
+    fn name(&self) -> &'static str {
+        COMMAND_NAMES
+            .iter()
+            .find(|(_, command)| command == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+}
+
+pub fn get_default_keymap() -> HashMap<String, KeyCommand> {
+    DEFAULT_BINDINGS
+        .iter()
+        .map(|(binding, command)| (binding.to_string(), *command))
+        .collect()
+}
+
+fn get_binding(key: &str, ctrl: bool, shift: bool, alt: bool) -> String {
+    let mut binding = String::new();
+    if ctrl {binding.push_str("ctrl+");}
+    if alt {binding.push_str("alt+");}
+    if shift {binding.push_str("shift+");}
+    binding.push_str(key.to_lowercase().as_str());
+    binding
+}
+
+fn normalize_binding(binding: &str) -> Option<String> {
+    let parts: Vec<String> = binding
+        .split('+')
+        .map(|part| part.trim().to_lowercase())
+        .collect();
+    let (key, modifiers) = parts.split_last()?;
+    if key.is_empty() || modifiers.iter().any(|modifier| modifier != "ctrl" && modifier != "shift" && modifier != "alt") {
+        return None;
+    }
+    let has = |name: &str| modifiers.iter().any(|modifier| modifier == name);
+    Some(get_binding(key, has("ctrl"), has("shift"), has("alt")))
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn on_key_down(&mut self, key: &str, ctrl: bool, shift: bool, alt: bool) -> std::result::Result<Option<String>, JsValue> {
+        let command = match self.keymap
+            .get(&get_binding(key, ctrl, shift, alt))
+            .or_else(|| if shift {self.keymap.get(&get_binding(key, ctrl, false, alt))} else {None})
+            .copied() {
+            Some(command) => command,
+            None => return Ok(None)
+        };
+
+        match command {
+            KeyCommand::CreateMode => {
+                self.clear_current_points();
+                self.set_create_state();
+                self.draw();
+            },
+            KeyCommand::EditMode => self.set_edit_state(),
+            KeyCommand::RulesMode => self.set_rules_state(),
+            KeyCommand::Cancel => self.cancel(),
+            KeyCommand::Delete => self.delete_hovered()?,
+            KeyCommand::FinishPath => {
+                match self.state {
+                    State::Create => {self.finish_path();},
+                    _ => return Ok(None)
+                }
+            },
+            KeyCommand::NudgeLeft => self.nudge_selection(-1.0, 0.0, shift)?,
+            KeyCommand::NudgeRight => self.nudge_selection(1.0, 0.0, shift)?,
+            KeyCommand::NudgeUp => self.nudge_selection(0.0, -1.0, shift)?,
+            KeyCommand::NudgeDown => self.nudge_selection(0.0, 1.0, shift)?,
+            KeyCommand::Undo | KeyCommand::Redo => {
+                match self.state {
+                    State::Moving(_) | State::MovingSelection(_) | State::Selecting(_) => return Ok(None),
+                    _ => {}
+                }
+                match (&self.state, command) {
+                    (State::Create, KeyCommand::Undo) if !self.current_points.is_empty() => self.remove_last_point(),
+                    (_, KeyCommand::Undo) => {self.undo();},
+                    _ => {self.redo();}
+                }
+            }
+        }
+        Ok(Some(command.name().to_string()))
+    }
+
+    pub fn bind_key(&mut self, binding: &str, command: &str) -> bool {
+        match (normalize_binding(binding), KeyCommand::from_name(command)) {
+            (Some(binding), Some(command)) => {
+                self.keymap.insert(binding, command);
+                true
+            },
+            _ => false
+        }
+    }
+
+    pub fn unbind_key(&mut self, binding: &str) {
+        normalize_binding(binding).map(|binding| self.keymap.remove(&binding));
+    }
+
+    pub fn reset_keymap(&mut self) {
+        self.keymap = get_default_keymap();
+    }
+}
+
+impl Canvas {
+    fn cancel(&mut self) {
+        match self.state {
+            State::Create => {
+                self.clear_current_points();
+                self.snap_target = None;
+            },
+            State::Moving(_) | State::MovingSelection(_) => {
+                if !self.discard_prepared_history() {
+                    self.revert_last_history();
+                }
+                self.state = State::Edit;
+            },
+            State::Rules(_) => {
+                self.selection.clear();
+                self.state = State::Rules(None);
+            },
+            State::Edit | State::Selecting(_) => {
+                self.selection.clear();
+                self.state = State::Edit;
+            }
+        }
+        self.draw();
+    }
+
+    fn delete_hovered(&mut self) -> Result<()> {
+        match self.state {
+            State::Create => {
+                self.remove_last_point();
+                return Ok(());
+            },
+            State::Edit | State::Rules(_) => {},
+            _ => return Ok(())
+        }
+        if !self.selection.is_empty() {
+            return self.remove_selection();
+        }
+        match self.get_hovered_object(self.cursor.0, self.cursor.1) {
+            Some((_, PressedObject::Point(point_id))) => {
+                self.save_history();
+                let result = self.remove_shared_point(point_id);
+                self.revert_failed_edit(result)?;
+            },
+            // removing an edge opens a closed polygon there, edges of open paths are kept
+            Some((i, PressedObject::Line(line_id, _))) if self.polygons[i].is_closed() => {
+                let mut polygon = self.polygons[i].clone();
+                let relation_updates = polygon.open_at(line_id)?;
+                self.save_history();
+                self.polygons[i] = polygon;
+                self.apply_relation_updates(relation_updates);
+                self.selection.clear();
+            },
+            Some((i, PressedObject::Center)) | Some((i, PressedObject::Inside(_))) => {
+                self.save_history();
+                let result = self.remove_polygon(i);
+                self.revert_failed_edit(result)?;
+            },
+            _ => return Ok(())
+        }
+        if let State::Rules(_) = self.state {
+            self.state = State::Rules(None);
+        }
+        self.draw();
+        Ok(())
+    }
+
+    fn nudge_selection(&mut self, dx: f64, dy: f64, large: bool) -> Result<()> {
+        if self.selection.is_empty() {
+            return Ok(());
+        }
+        let step = (if large {NUDGE_LARGE_STEP} else {NUDGE_STEP})/self.viewport.scale;
+        self.save_history();
+        let result = self.transform_selection(&Transform::translation(dx*step, dy*step));
+        self.draw();
+        self.revert_failed_edit(result)
+    }
+}
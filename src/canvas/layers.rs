@@ -0,0 +1,226 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::{Layer, PointCords};
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::scene::{escape_json, get_default_layers};
+use super::{Canvas, State, PressedObject, SelectedObject};
+
+#[wasm_bindgen]
+impl Canvas {
+    /// Layers from the bottom one as JSON: [{"id", "name", "visible", "locked", "active"}].
+    pub fn get_layers(&self) -> String {
+        let layers: Vec<String> = self.layers
+            .iter()
+            .map(|layer| format!(
+                "{{\"id\":{},\"name\":\"{}\",\"visible\":{},\"locked\":{},\"active\":{}}}",
+                layer.id, escape_json(&layer.name), layer.visible, layer.locked, layer.id == self.active_layer
+            ))
+            .collect();
+        format!("[{}]", layers.join(","))
+    }
+
+    /// Adds a layer on top of the others, new polygons are drawn on it.
+    pub fn add_layer(&mut self, name: &str) -> u32 {
+        let id = self.layers.iter().map(|layer| layer.id + 1).max().unwrap_or(0);
+        self.save_history();
+        self.layers.push(Layer { id, name: name.to_string(), visible: true, locked: false });
+        self.active_layer = id;
+        id
+    }
+
+    /// Polygons of a removed layer move to the layer below it, the last layer cannot be removed.
+    pub fn remove_layer(&mut self, id: u32) -> std::result::Result<(), JsValue> {
+        let position = self.get_layer_position(id)?;
+        if self.layers.len() == 1 {
+            return Err(EditorError::InvalidLayer(id).into());
+        }
+        let target = self.layers[if position == 0 {1} else {position - 1}].id;
+        self.save_history();
+        self.polygons
+            .iter_mut()
+            .filter(|polygon| polygon.layer == id)
+            .for_each(|polygon| polygon.layer = target);
+        self.layers.remove(position);
+        if self.active_layer == id {
+            self.active_layer = target;
+        }
+        self.sort_polygons_by_layer();
+        self.draw();
+        Ok(())
+    }
+
+    pub fn rename_layer(&mut self, id: u32, name: &str) -> std::result::Result<(), JsValue> {
+        let position = self.get_layer_position(id)?;
+        self.save_history();
+        self.layers[position].name = name.to_string();
+        Ok(())
+    }
+
+    pub fn set_active_layer(&mut self, id: u32) -> std::result::Result<(), JsValue> {
+        self.get_layer_position(id)?;
+        self.active_layer = id;
+        Ok(())
+    }
+
+    pub fn set_layer_visible(&mut self, id: u32, visible: bool) -> std::result::Result<(), JsValue> {
+        let position = self.get_layer_position(id)?;
+        self.layers[position].visible = visible;
+        self.deselect_locked();
+        self.draw();
+        Ok(())
+    }
+
+    pub fn set_layer_locked(&mut self, id: u32, locked: bool) -> std::result::Result<(), JsValue> {
+        let position = self.get_layer_position(id)?;
+        self.layers[position].locked = locked;
+        self.deselect_locked();
+        self.draw();
+        Ok(())
+    }
+
+    /// Moves a layer up (positive offset) or down in the drawing order.
+    pub fn move_layer(&mut self, id: u32, offset: i32) -> std::result::Result<(), JsValue> {
+        let position = self.get_layer_position(id)?;
+        let target = (position as i32 + offset).max(0).min(self.layers.len() as i32 - 1) as usize;
+        if target == position {
+            return Ok(());
+        }
+        self.save_history();
+        let layer = self.layers.remove(position);
+        self.layers.insert(target, layer);
+        self.sort_polygons_by_layer();
+        self.draw();
+        Ok(())
+    }
+
+    /// Moves the selected polygons, or the polygon of the selected line, to the layer.
+    pub fn move_selection_to_layer(&mut self, id: u32) -> std::result::Result<(), JsValue> {
+        self.get_layer_position(id)?;
+        let mut polygon_ids: Vec<usize> = self.selection
+            .iter()
+            .map(|(polygon_id, _)| *polygon_id)
+            .chain(self.get_selected_polygon())
+            .collect();
+        polygon_ids.sort();
+        polygon_ids.dedup();
+        if polygon_ids.is_empty() {
+            return Ok(());
+        }
+        self.save_history();
+        for polygon_id in polygon_ids {
+            self.get_polygon_mut(polygon_id)?.layer = id;
+        }
+        self.sort_polygons_by_layer();
+        self.deselect_locked();
+        self.draw();
+        Ok(())
+    }
+}
+
+impl Canvas {
+    pub fn get_layer_position(&self, id: u32) -> Result<usize> {
+        self.layers
+            .iter()
+            .position(|layer| layer.id == id)
+            .ok_or(EditorError::InvalidLayer(id))
+    }
+
+    pub fn is_visible(&self, polygon_id: usize) -> bool {
+        self.layers
+            .iter()
+            .find(|layer| layer.id == self.polygons[polygon_id].layer)
+            .is_none_or(|layer| layer.visible)
+    }
+
+    /// Polygons of hidden or locked layers are ignored by hit testing.
+    pub fn is_editable(&self, polygon_id: usize) -> bool {
+        self.layers
+            .iter()
+            .find(|layer| layer.id == self.polygons[polygon_id].layer)
+            .is_none_or(|layer| layer.visible && !layer.locked)
+    }
+
+    pub fn check_polygon_hover(&self, polygon_id: usize, x: f64, y: f64) -> Option<PressedObject> {
+        if !self.is_editable(polygon_id) {
+            return None;
+        }
+        self.polygons[polygon_id].check_hover(x, y, self.viewport.scale)
+    }
+
+    pub fn contains_editable_point(&self, polygon_id: usize, p: PointCords) -> bool {
+        self.is_editable(polygon_id) && self.polygons[polygon_id].contains_point(p.0, p.1)
+    }
+
+    /// Adds a polygon on top of the other polygons of its layer, the selection is cleared when indices change.
+    pub fn push_polygon(&mut self, mut polygon: Polygon, layer: u32) -> usize {
+        polygon.layer = layer;
+        let position = self.get_layer_position(layer).unwrap_or(0);
+        let index = self.polygons
+            .iter()
+            .rposition(|other| self.get_layer_position(other.layer).unwrap_or(0) <= position)
+            .map_or(0, |index| index + 1);
+        if index < self.polygons.len() {
+            self.selection.clear();
+            self.deselect_line();
+        }
+        self.polygons.insert(index, polygon);
+        index
+    }
+
+    /// Keeps the polygons in drawing order: by the position of their layer, the order within a layer stays.
+    pub fn sort_polygons_by_layer(&mut self) {
+        let positions: Vec<usize> = self.polygons
+            .iter()
+            .map(|polygon| self.get_layer_position(polygon.layer).unwrap_or(0))
+            .collect();
+        if positions.windows(2).all(|pair| pair[0] <= pair[1]) {
+            return;
+        }
+        let mut indices: Vec<usize> = (0..self.polygons.len()).collect();
+        indices.sort_by_key(|i| positions[*i]);
+        self.polygons = indices
+            .iter()
+            .map(|i| self.polygons[*i].clone())
+            .collect();
+        self.selection.clear();
+        self.deselect_line();
+    }
+
+    /// Polygons of layers that no longer exist, e.g. after undoing past a layer removal, go to the first layer.
+    pub fn normalize_layers(&mut self) {
+        if self.layers.is_empty() {
+            self.layers = get_default_layers();
+        }
+        let first = self.layers[0].id;
+        for i in 0..self.polygons.len() {
+            if self.get_layer_position(self.polygons[i].layer).is_err() {
+                self.polygons[i].layer = first;
+            }
+        }
+        if self.get_layer_position(self.active_layer).is_err() {
+            self.active_layer = first;
+        }
+        self.sort_polygons_by_layer();
+    }
+
+    fn deselect_locked(&mut self) {
+        let selection: Vec<(usize, SelectedObject)> = self.selection
+            .iter()
+            .filter(|(polygon_id, _)| self.is_editable(*polygon_id))
+            .copied()
+            .collect();
+        self.selection = selection;
+        match self.state {
+            State::Rules(Some((polygon_id, _))) if !self.is_editable(polygon_id) => self.deselect_line(),
+            _ => {}
+        }
+    }
+
+    fn deselect_line(&mut self) {
+        if let State::Rules(Some(_)) = self.state {
+            self.state = State::Rules(None);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::{Point, PointCords};
+use crate::error::{EditorError, Result};
+use crate::utils::check_point_hover;
+use super::{Canvas, State};
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn merge_along_selected_line(&mut self) -> std::result::Result<bool, JsValue> {
+        let (polygon_id, line_id) = match self.get_selected_lines().first() {
+            Some(selected) => *selected,
+            None => return Ok(false)
+        };
+        let polygon = self.get_polygon(polygon_id)?;
+        let mut other_id: Option<usize> = None;
+        for j in 0..self.polygons.len() {
+            if j != polygon_id && self.is_editable(j) && polygon.find_shared_line(&self.polygons[j], line_id)?.is_some() {
+                other_id = Some(j);
+                break;
+            }
+        }
+        let other_id = match other_id {
+            Some(other_id) => other_id,
+            None => return Ok(false)
+        };
+
+        let (merged, relation_updates) = polygon.merge_along(&self.polygons[other_id], line_id)?;
+        self.save_history();
+        self.polygons[polygon_id] = merged;
+        self.polygons.remove(other_id);
+        self.apply_relation_updates(relation_updates);
+        self.selection.clear();
+        self.state = State::Rules(None);
+        self.draw();
+        Ok(true)
+    }
+}
+
+impl Canvas {
+    /// The topmost editable vertex other than the given one lying under it.
+    pub fn get_drop_target(&self, polygon_id: usize, point_id: u32) -> Option<(usize, Point)> {
+        let source = self.get_polygon(polygon_id).ok()?.get_point_by_id(point_id).ok()?;
+        (0..self.polygons.len())
+            .rev()
+            .filter(|i| self.is_editable(*i))
+            .find_map(|i| self.polygons[i].points
+                .iter()
+                .filter(|point| point.id != point_id)
+                .find(|point| check_point_hover(PointCords(point.x, point.y), source, self.viewport.scale))
+                .map(|point| (i, *point)))
+    }
+
+    /// Merges a dragged vertex into the vertex it was dropped on, unless it already lay on that vertex when
+    /// the drag started. Adjacent vertices of one polygon collapse their edge, a vertex of another polygon
+    /// is replaced by the target in every polygon using it. Merges that are not possible leave the drop as it is.
+    pub fn merge_dropped_point(&mut self, polygon_id: usize, point_id: u32) -> Result<()> {
+        let origin = self.drop_origin.take();
+        let target = match self.get_drop_target(polygon_id, point_id) {
+            Some((_, target)) if Some(target.id) != origin => target,
+            _ => return Ok(())
+        };
+
+        if self.polygons[polygon_id].points.iter().any(|point| point.id == target.id) {
+            match self.polygons[polygon_id].merge_points(point_id, target.id) {
+                Ok(relation_updates) => self.apply_relation_updates(relation_updates),
+                Err(EditorError::InvalidMerge(_)) => return Ok(()),
+                Err(error) => return Err(error)
+            }
+            self.selection.clear();
+            return Ok(());
+        }
+
+        let users: Vec<usize> = (0..self.polygons.len())
+            .filter(|i| self.polygons[*i].points.iter().any(|point| point.id == point_id))
+            .collect();
+        if users.iter().any(|i| self.polygons[*i].points.iter().any(|point| point.id == target.id)) {
+            return Ok(());
+        }
+        let before = (self.polygons.clone(), self.shared.clone());
+        for i in users.iter() {
+            self.polygons[*i].replace_point(point_id, target);
+        }
+        self.weld_lines();
+        self.refresh_shared();
+        let mut result = Ok(());
+        for i in users {
+            result = result
+                .and_then(|_| self.correct_line_length(target.id, i, false))
+                .and_then(|_| self.correct_line_length(target.id, i, true));
+            self.reset_visited();
+        }
+        if result.and_then(|_| self.commit_shared()).is_err() {
+            // the constraints of the dragged polygons cannot reach the target, the drop stays unmerged
+            (self.polygons, self.shared) = before;
+        }
+        self.selection.clear();
+        self.recalculate();
+        Ok(())
+    }
+
+    pub fn apply_relation_updates(&mut self, relation_updates: Vec<(u32, Option<u32>)>) {
+        for (line_id, relation) in relation_updates {
+            for h in 0..self.polygons.len() {
+                self.polygons[h].set_relation(line_id, relation);
+            }
+        }
+    }
+}
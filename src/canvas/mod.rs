@@ -5,23 +5,61 @@ use web_sys::HtmlInputElement;
 use web_sys::CanvasRenderingContext2d;
 use crate::polygon::*;
 use crate::data_models::*;
+use crate::ids::IdAllocator;
+use snapping::{SnapSettings, SnapTarget};
+use viewport::Viewport;
+use history::History;
+use keyboard::{KeyCommand, get_default_keymap};
+use topology::SharedRecords;
+use freehand::FreehandSettings;
+use utils::MeshCache;
+use crate::units::Units;
+use crate::scene::get_default_layers;
+use std::collections::HashMap;
+use std::cell::RefCell;
 
 pub mod handlers;
 pub mod draw;
 pub mod utils;
 pub mod predefined;
+pub mod operations;
+pub mod selection;
+pub mod snapping;
+pub mod viewport;
+pub mod history;
+pub mod keyboard;
+pub mod persistence;
+pub mod merge;
+pub mod topology;
+pub mod shapes;
+pub mod freehand;
+pub mod dimensions;
+pub mod units;
+pub mod layers;
 
 pub enum State{
     Create,
     Edit,
     Rules(Option<(usize, u32)>),
-    Moving((usize, PressedObject))
+    Moving((usize, PressedObject)),
+    Selecting((PointCords, bool)),
+    MovingSelection(PointCords)
 }
 
 pub enum PressedObject {
     Center,
     Line(u32,(f64, f64)),
     BesierLine(u32, u32),
+    Point(u32),
+    Inside((f64, f64)),
+    RotationHandle(f64),
+    ScaleHandle(f64)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelectedObject {
+    Polygon,
+    Line(u32),
     Point(u32)
 }
 
@@ -30,8 +68,29 @@ pub struct Canvas{
    context: CanvasRenderingContext2d,
    state: State,
    current_points: Vec<Point>,
+   shape_tool: ShapeTool,
+   shape_anchor: Option<PointCords>,
+   sketch: Vec<PointCords>,
+   freehand: FreehandSettings,
    polygons: Vec<Polygon>,
-   current_id: u32,
+   layers: Vec<Layer>,
+   active_layer: u32,
+   ids: IdAllocator,
+   shared: SharedRecords,
+   shared_topology: bool,
+   centroid_mode: CentroidMode,
+   show_triangulation: bool,
+   mesh_cache: RefCell<Option<MeshCache>>,
+   pivot: Option<PointCords>,
+   drop_origin: Option<u32>,
+   selection: Vec<(usize, SelectedObject)>,
+   snap: SnapSettings,
+   snap_target: Option<(PointCords, SnapTarget)>,
+   viewport: Viewport,
+   units: Units,
+   history: History,
+   keymap: HashMap<String, KeyCommand>,
+   cursor: PointCords,
    length_selector: HtmlInputElement,
    is_const: HtmlInputElement
 }
@@ -68,8 +127,29 @@ impl Canvas {
             context,
             state: State::Create,
             current_points: vec![],
-            current_id: 1,
+            shape_tool: ShapeTool::Polygon,
+            shape_anchor: None,
+            sketch: vec![],
+            freehand: FreehandSettings::new(),
+            ids: IdAllocator::new(),
+            shared: SharedRecords::new(),
+            shared_topology: false,
+            centroid_mode: CentroidMode::Area,
+            show_triangulation: false,
+            mesh_cache: RefCell::new(None),
+            pivot: None,
+            drop_origin: None,
+            selection: vec![],
+            snap: SnapSettings::new(),
+            snap_target: None,
+            viewport: Viewport::new(),
+            units: Units::new(),
+            history: History::new(),
+            keymap: get_default_keymap(),
+            cursor: PointCords(0.0, 0.0),
             polygons: vec![],
+            layers: get_default_layers(),
+            active_layer: 0,
             is_const,
             length_selector: num_field
         }
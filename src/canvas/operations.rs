@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::{CornerStyle, JoinStyle, PointCords, SimplifyMethod};
+use crate::error::EditorError;
+use crate::polygon::Polygon;
+use crate::transform::Transform;
+use crate::scene::scene_to_svg;
+use crate::triangulation::{meshes_to_json, meshes_to_obj};
+use super::{Canvas, State, SelectedObject};
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn is_selected_convex(&self) -> Option<bool> {
+        self.get_selected_polygon()
+            .filter(|polygon_id| self.polygons[*polygon_id].is_closed())
+            .map(|polygon_id| self.polygons[polygon_id].is_convex())
+    }
+
+    pub fn make_convex_hull(&mut self) {
+        if let Some(polygon_id) = self.get_selected_polygon() {
+            let hull = self.polygons[polygon_id].convex_hull();
+            let layer = self.polygons[polygon_id].layer;
+            self.save_history();
+            self.add_polygon(hull, layer);
+            self.state = State::Rules(None);
+            self.draw();
+        }
+    }
+
+    pub fn decompose_convex(&mut self) -> Result<(), JsValue> {
+        if let Some(polygon_id) = self.get_selected_polygon().filter(|polygon_id| self.polygons[*polygon_id].is_closed()) {
+            let pieces = self.polygons[polygon_id].convex_decomposition()?;
+            if pieces.len() <= 1 {
+                return Ok(());
+            }
+            let layer = self.polygons[polygon_id].layer;
+            self.save_history();
+            self.remove_polygon(polygon_id)?;
+            pieces
+                .into_iter()
+                .for_each(|piece| {self.add_polygon(piece, layer);});
+            self.state = State::Rules(None);
+            self.draw();
+        }
+        Ok(())
+    }
+
+    pub fn offset_polygon(&mut self, distance: f64, join_style: &str, miter_limit: f64) -> Result<(), JsValue> {
+        if let Some(polygon_id) = self.get_selected_polygon().filter(|polygon_id| self.polygons[*polygon_id].is_closed()) {
+            let join_style = match join_style {
+                "round" => JoinStyle::Round,
+                "bevel" => JoinStyle::Bevel,
+                _ => JoinStyle::Miter
+            };
+            let outlines = self.polygons[polygon_id].offset(distance, join_style, miter_limit)?;
+            if outlines.is_empty() {
+                return Ok(());
+            }
+            let layer = self.polygons[polygon_id].layer;
+            self.save_history();
+            outlines
+                .into_iter()
+                .for_each(|outline| {self.add_polygon(outline, layer);});
+            self.state = State::Rules(None);
+            self.draw();
+        }
+        Ok(())
+    }
+
+    /// Vertices shared with other polygons are never removed. Returns the number of removed vertices.
+    pub fn simplify_polygon(&mut self, method: &str, tolerance: f64) -> Result<u32, JsValue> {
+        let polygon_id = match self.get_selected_polygon() {
+            Some(polygon_id) => polygon_id,
+            None => return Ok(0)
+        };
+        let method = match method {
+            "visvalingam" => SimplifyMethod::Visvalingam,
+            _ => SimplifyMethod::DouglasPeucker
+        };
+        let pinned: HashSet<u32> = self.polygons
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != polygon_id)
+            .flat_map(|(_, polygon)| polygon.points.iter().map(|point| point.id))
+            .collect();
+        let mut simplified = self.polygons[polygon_id].clone();
+        let removed = simplified.simplify(method, tolerance, &pinned)?;
+        if removed > 0 {
+            self.save_history();
+            self.polygons[polygon_id] = simplified;
+            self.state = State::Rules(None);
+            self.recalculate();
+            self.draw();
+        }
+        Ok(removed as u32)
+    }
+
+    /// Rounds or cuts every selected vertex, edges shared with other polygons cannot be shortened.
+    pub fn round_selected_points(&mut self, style: &str, size: f64) -> Result<(), JsValue> {
+        let style = match style {
+            "chamfer" => CornerStyle::Chamfer,
+            _ => CornerStyle::Fillet
+        };
+        let points: Vec<(usize, u32)> = self.selection
+            .iter()
+            .filter_map(|(polygon_id, object)| match object {
+                SelectedObject::Point(point_id) => Some((*polygon_id, *point_id)),
+                _ => None
+            })
+            .collect();
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        let mut polygons = self.polygons.clone();
+        let mut ids = self.ids;
+        for (polygon_id, point_id) in points {
+            let polygon = polygons.get_mut(polygon_id).ok_or(EditorError::PolygonNotFound(polygon_id))?;
+            let is_shared = polygon.lines
+                .iter()
+                .filter(|line| line.points.0 == point_id || line.points.1 == point_id)
+                .any(|line| self.is_shared_line(polygon_id, line.id));
+            if is_shared {
+                return Err(EditorError::InvalidCorner(point_id).into());
+            }
+            polygon.round_corner(point_id, style, size, &mut ids)?;
+        }
+        self.save_history();
+        self.polygons = polygons;
+        self.ids = ids;
+        self.selection.clear();
+        self.recalculate();
+        self.draw();
+        Ok(())
+    }
+
+    pub fn set_pivot(&mut self, x: f64, y: f64) {
+        self.pivot = Some(PointCords(x, y));
+        self.draw();
+    }
+
+    pub fn clear_pivot(&mut self) {
+        self.pivot = None;
+        self.draw();
+    }
+
+    pub fn rotate_polygon(&mut self, angle: f64) -> Result<(), JsValue> {
+        Ok(self.transform_selected(|pivot| Transform::rotation(angle.to_radians(), pivot))?)
+    }
+
+    pub fn scale_polygon(&mut self, sx: f64, sy: f64) -> Result<(), JsValue> {
+        Ok(self.transform_selected(|pivot| Transform::scale(sx, sy, pivot))?)
+    }
+
+    pub fn shear_polygon(&mut self, kx: f64, ky: f64) -> Result<(), JsValue> {
+        Ok(self.transform_selected(|pivot| Transform::shear(kx, ky, pivot))?)
+    }
+
+    pub fn mirror_polygon(&mut self, axis_angle: f64) -> Result<(), JsValue> {
+        Ok(self.transform_selected(|pivot| Transform::mirror(axis_angle.to_radians(), pivot))?)
+    }
+
+    pub fn set_show_triangulation(&mut self, show: bool) {
+        self.show_triangulation = show;
+        self.draw();
+    }
+
+    pub fn export_triangles_json(&self) -> String {
+        meshes_to_json(&self.get_meshes())
+    }
+
+    pub fn export_triangles_obj(&self) -> String {
+        meshes_to_obj(&self.get_meshes())
+    }
+
+    pub fn export_svg(&self) -> String {
+        let polygons: Vec<Polygon> = (0..self.polygons.len())
+            .filter(|i| self.is_visible(*i))
+            .map(|i| self.polygons[i].clone())
+            .collect();
+        scene_to_svg(&polygons, &self.units)
+    }
+}
+
+impl Canvas {
+    fn transform_selected<F: Fn(PointCords) -> Transform>(&mut self, get_transform: F) -> crate::error::Result<()> {
+        if let Some(center) = self.get_selection_center() {
+            let transform = get_transform(self.pivot.unwrap_or(center));
+            self.save_history();
+            let result = self.transform_selection(&transform);
+            self.draw();
+            return self.revert_failed_edit(result);
+        }
+        if let Some(polygon_id) = self.get_selected_polygon() {
+            let transform = get_transform(self.get_pivot(polygon_id));
+            self.save_history();
+            self.polygons[polygon_id].apply_transform(&transform);
+            let result = self.commit_shared();
+            self.recalculate();
+            self.draw();
+            return self.revert_failed_edit(result);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::scene::{scene_from_json, scene_to_json};
+use super::{Canvas, State};
+use super::topology::SharedRecords;
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn save_scene(&self) -> String {
+        scene_to_json(&self.polygons, self.ids.peek(), &self.units, &self.layers)
+    }
+
+    pub fn load_scene(&mut self, json: &str) -> Result<(), JsValue> {
+        let scene = scene_from_json(json)?;
+        let mut ids = self.ids;
+        ids.reserve(scene.next_id.saturating_sub(1))?;
+        ids.reserve_polygons(&scene.polygons)?;
+        self.save_history();
+        self.polygons = scene.polygons;
+        self.units = scene.units;
+        self.layers = scene.layers;
+        self.active_layer = self.layers.last().map_or(0, |layer| layer.id);
+        self.normalize_layers();
+        self.shared = SharedRecords::new();
+        self.refresh_shared();
+        self.ids = ids;
+        self.clear_current_points();
+        self.selection.clear();
+        self.state = match self.state {
+            State::Create => State::Create,
+            State::Rules(_) => State::Rules(None),
+            _ => State::Edit
+        };
+        self.draw();
+        Ok(())
+    }
+}
@@ -1,77 +1,68 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::{draw::clear_canvas, data_models::Point, utils::{calcualate_new_lines, get_centroid}, polygon::Polygon};
+use crate::{draw::clear_canvas, data_models::Point, utils::calcualate_new_lines, polygon::Polygon};
 
 use super::Canvas;
 
 #[wasm_bindgen]
 impl Canvas {
     pub fn set_predefined_scene(&mut self){
+        self.save_history();
         clear_canvas(&self.context);
         let points1 = vec![
             Point {
                 x: 100.0,
                 y: 100.0,
-                id: 1
+                id: self.ids.allocate()
             },
             Point {
                 x: 200.0,
                 y: 200.0,
-                id: 2
+                id: self.ids.allocate()
             },
             Point {
                 x: 160.0,
                 y: 350.0,
-                id: 3
+                id: self.ids.allocate()
             },
         ];
 
-        let mut lines1 = calcualate_new_lines(points1.iter().collect());
-        let center1 = get_centroid(&points1);
+        let mut lines1 = calcualate_new_lines(points1.iter().collect(), &mut self.ids);
 
         let points2 = vec![
             Point {
                 x: 300.0,
                 y: 200.0,
-                id: 5
+                id: self.ids.allocate()
             },
             Point {
                 x: 200.0,
                 y: 100.0,
-                id: 4
+                id: self.ids.allocate()
             },
             Point {
                 x: 50.0,
                 y: 150.0,
-                id: 7
+                id: self.ids.allocate()
             },
             Point {
                 x: 220.0,
                 y: 300.0,
-                id: 6
+                id: self.ids.allocate()
             },
         ];
 
-        let mut lines2 = calcualate_new_lines(points2.iter().collect());
-        let center2 = get_centroid(&points2);
+        let mut lines2 = calcualate_new_lines(points2.iter().collect(), &mut self.ids);
 
         lines2[1].is_const = true;
         lines2[1].relation = Some(lines1[1].id);
         lines1[1].relation = Some(lines2[1].id);
 
-        let polygon1 = Polygon {
-            points: points1,
-            lines: lines1,
-            center: center1 
-        };
-
-        let polygon2 = Polygon {
-            points: points2,
-            lines: lines2,
-            center: center2 
-        };
+        let polygon1 = Polygon::new(points1, lines1, self.centroid_mode);
+        let polygon2 = Polygon::new(points2, lines2, self.centroid_mode);
 
         self.polygons = vec![polygon1, polygon2];
+        self.normalize_layers();
         self.current_points = vec![];
         self.recalculate();
         self.draw();
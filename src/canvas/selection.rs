@@ -0,0 +1,222 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::PointCords;
+use crate::error::Result;
+use crate::transform::Transform;
+use super::{Canvas, State, PressedObject, SelectedObject};
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.draw();
+    }
+
+    pub fn delete_selection(&mut self) -> std::result::Result<(), JsValue> {
+        Ok(self.remove_selection()?)
+    }
+}
+
+impl Canvas {
+    pub fn remove_selection(&mut self) -> Result<()> {
+        let selected: Vec<(usize, Vec<u32>)> = (0..self.polygons.len())
+            .map(|polygon_id| (polygon_id, self.get_selected_point_ids(polygon_id)))
+            .filter(|(_, point_ids)| !point_ids.is_empty())
+            .collect();
+        if selected.is_empty() {
+            return Ok(());
+        }
+        self.save_history();
+
+        let removed_ids: Vec<u32> = self.selection
+            .iter()
+            .filter_map(|(_, object)| match object {
+                SelectedObject::Point(point_id) => Some(*point_id),
+                _ => None
+            })
+            .collect();
+        let result = self.remove_points(selected, removed_ids);
+        self.revert_failed_edit(result)?;
+
+        self.selection.clear();
+        self.state = State::Edit;
+        self.draw();
+        Ok(())
+    }
+
+    /// Polygons that would keep too few vertices are removed whole.
+    fn remove_points(&mut self, selected: Vec<(usize, Vec<u32>)>, removed_ids: Vec<u32>) -> Result<()> {
+        for (polygon_id, point_ids) in selected.into_iter().rev() {
+            if point_ids.len() + self.polygons[polygon_id].get_min_points_count() > self.polygons[polygon_id].points.len() {
+                self.remove_polygon(polygon_id)?;
+                continue;
+            }
+            for point_id in point_ids {
+                self.remove_point(polygon_id, point_id)?;
+            }
+        }
+        for point_id in removed_ids {
+            self.remove_shared_point(point_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_selected_object(pressed_object: &PressedObject) -> Option<SelectedObject> {
+        match pressed_object {
+            PressedObject::Center | PressedObject::Inside(_) => Some(SelectedObject::Polygon),
+            PressedObject::Line(line_id, _) => Some(SelectedObject::Line(*line_id)),
+            PressedObject::Point(point_id) => Some(SelectedObject::Point(*point_id)),
+            _ => None
+        }
+    }
+
+    pub fn toggle_selection(&mut self, polygon_id: usize, object: SelectedObject) {
+        let position = self.selection.iter().position(|selected| *selected == (polygon_id, object));
+        match position {
+            Some(position) => {self.selection.remove(position);},
+            None => {self.selection.push((polygon_id, object));}
+        }
+    }
+
+    pub fn is_selected(&self, polygon_id: usize, pressed_object: &PressedObject) -> bool {
+        match Canvas::get_selected_object(pressed_object) {
+            Some(object) => self.selection
+                .iter()
+                .any(|selected| *selected == (polygon_id, object) || *selected == (polygon_id, SelectedObject::Polygon)),
+            None => false
+        }
+    }
+
+    pub fn select_in_rect(&mut self, a: PointCords, b: PointCords, add: bool) {
+        if !add {
+            self.selection.clear();
+        }
+        let is_inside = |x: f64, y: f64| x >= a.0.min(b.0) && x <= a.0.max(b.0) && y >= a.1.min(b.1) && y <= a.1.max(b.1);
+
+        for i in 0..self.polygons.len() {
+            if !self.is_editable(i) {
+                continue;
+            }
+            let inside: Vec<u32> = self.polygons[i].points
+                .iter()
+                .filter(|point| is_inside(point.x, point.y))
+                .map(|point| point.id)
+                .collect();
+            if inside.is_empty() {
+                continue;
+            }
+            if inside.len() == self.polygons[i].points.len() {
+                if !self.selection.contains(&(i, SelectedObject::Polygon)) {
+                    self.selection.push((i, SelectedObject::Polygon));
+                }
+                continue;
+            }
+            inside
+                .iter()
+                .for_each(|point_id| {
+                    if !self.selection.contains(&(i, SelectedObject::Point(*point_id))) {
+                        self.selection.push((i, SelectedObject::Point(*point_id)));
+                    }
+                });
+        }
+    }
+
+    pub fn get_selected_point_ids(&self, polygon_id: usize) -> Vec<u32> {
+        let polygon = &self.polygons[polygon_id];
+        let mut point_ids: Vec<u32> = vec![];
+        self.selection
+            .iter()
+            .filter(|(i, _)| *i == polygon_id)
+            .for_each(|(_, object)| {
+                match object {
+                    SelectedObject::Polygon => point_ids.extend(polygon.points.iter().map(|point| point.id)),
+                    SelectedObject::Point(point_id) => point_ids.push(*point_id),
+                    SelectedObject::Line(line_id) => {
+                        if let Ok((p1, p2)) = polygon.get_line_by_id(*line_id) {
+                            point_ids.push(p1);
+                            point_ids.push(p2);
+                        }
+                    }
+                }
+            });
+        point_ids.sort();
+        point_ids.dedup();
+        point_ids.retain(|point_id| polygon.points.iter().any(|point| point.id == *point_id));
+        point_ids
+    }
+
+    pub fn get_selected_lines(&self) -> Vec<(usize, u32)> {
+        let mut lines: Vec<(usize, u32)> = match self.state {
+            State::Rules(Some(line)) => vec![line],
+            _ => vec![]
+        };
+        self.selection
+            .iter()
+            .for_each(|(polygon_id, object)| {
+                match object {
+                    SelectedObject::Line(line_id) if !lines.contains(&(*polygon_id, *line_id)) => lines.push((*polygon_id, *line_id)),
+                    _ => {}
+                }
+            });
+        lines
+    }
+
+    pub fn get_selection_center(&self) -> Option<PointCords> {
+        let mut sum = PointCords(0.0, 0.0);
+        let mut count = 0.0;
+        for i in 0..self.polygons.len() {
+            self.get_selected_point_ids(i)
+                .iter()
+                .filter_map(|point_id| self.polygons[i].get_point_by_id(*point_id).ok())
+                .for_each(|p| {
+                    sum = PointCords(sum.0 + p.0, sum.1 + p.1);
+                    count += 1.0;
+                });
+        }
+        if count == 0.0 {
+            return None;
+        }
+        Some(PointCords(sum.0/count, sum.1/count))
+    }
+
+    pub fn transform_selection(&mut self, transform: &Transform) -> Result<()> {
+        for i in 0..self.polygons.len() {
+            let point_ids = self.get_selected_point_ids(i);
+            if point_ids.is_empty() {
+                continue;
+            }
+            if point_ids.len() == self.polygons[i].points.len() {
+                self.polygons[i].apply_transform(transform);
+                continue;
+            }
+
+            for point_id in point_ids.iter() {
+                let point = self.polygons[i].get_point_reference(*point_id)?;
+                let PointCords(x, y) = transform.apply(PointCords(point.x, point.y));
+                point.x = x;
+                point.y = y;
+            }
+            self.polygons[i].lines
+                .iter_mut()
+                .filter(|line| point_ids.contains(&line.points.0) && point_ids.contains(&line.points.1))
+                .for_each(|line| {
+                    line.bezier = line.bezier.map(|(b1, b2)| (transform.apply(b1), transform.apply(b2)));
+                });
+            let result = point_ids
+                .iter()
+                .try_for_each(|point_id| {
+                    self.correct_line_length(*point_id, i, false)?;
+                    self.correct_line_length(*point_id, i, true)
+                });
+            self.reset_visited();
+            if result.is_err() {
+                self.recalculate();
+                return result;
+            }
+        }
+        let result = self.commit_shared();
+        self.recalculate();
+        result
+    }
+}
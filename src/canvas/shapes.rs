@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::data_models::{PointCords, ShapeTool};
+use crate::error::EditorError;
+use crate::shapes::create_shape;
+use super::Canvas;
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn set_shape_tool(&mut self, tool: &str, sides: u32) -> Result<(), JsValue> {
+        let tool = match tool {
+            "regular" if sides < 3 => return Err(EditorError::InvalidShape("regular polygon needs at least 3 sides").into()),
+            "regular" => ShapeTool::RegularPolygon(sides),
+            "rectangle" => ShapeTool::Rectangle,
+            "ellipse" => ShapeTool::Ellipse,
+            "circle" => ShapeTool::Circle,
+            "freehand" => ShapeTool::Freehand,
+            _ => ShapeTool::Polygon
+        };
+        self.shape_tool = tool;
+        self.clear_current_points();
+        self.draw();
+        Ok(())
+    }
+}
+
+impl Canvas {
+    /// The first click of a shape tool places the anchor, the second one adds the shape.
+    pub fn add_shape_point(&mut self, p: PointCords) {
+        match self.shape_anchor.take() {
+            None => self.shape_anchor = Some(p),
+            Some(anchor) => match create_shape(self.shape_tool, anchor, p, &mut self.ids, self.centroid_mode) {
+                Ok(polygon) => {
+                    self.save_history();
+                    self.push_polygon(polygon, self.active_layer);
+                    if self.shared_topology {
+                        self.weld_shared();
+                    }
+                    self.snap_target = None;
+                },
+                // a click too close to the anchor does not finish the shape
+                Err(_) => self.shape_anchor = Some(anchor)
+            }
+        }
+        self.draw();
+    }
+
+    pub fn draw_shape_preview(&self, anchor: PointCords) {
+        let p = self.snap_target.map_or(self.cursor, |(p, _)| p);
+        let mut ids = self.ids;
+        if let Ok(preview) = create_shape(self.shape_tool, anchor, p, &mut ids, self.centroid_mode) {
+            preview.draw(&self.context, 0, &mut HashMap::new(), None, &self.units);
+        }
+    }
+}
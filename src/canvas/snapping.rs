@@ -0,0 +1,217 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::data_models::PointCords;
+use crate::utils::{calculate_middle_point, get_bezier_point, get_distance_to_segment, get_line_length, get_projection_on_segment, get_segments_intersection, BEZIER_SEGMENTS};
+use super::Canvas;
+
+const SNAP_DISTANCE: f64 = 10.0;
+const ANGLE_STEP: f64 = 15.0;
+const DEFAULT_GRID_SIZE: f64 = 20.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SnapTarget {
+    Grid,
+    Vertex,
+    Midpoint,
+    Edge,
+    Intersection,
+    Angle
+}
+
+pub struct SnapSettings {
+    pub grid: bool,
+    pub grid_size: f64,
+    pub vertices: bool,
+    pub midpoints: bool,
+    pub edges: bool,
+    pub intersections: bool,
+    pub angles: bool
+}
+
+impl SnapSettings {
+    pub fn new() -> SnapSettings {
+        SnapSettings {
+            grid: false,
+            grid_size: DEFAULT_GRID_SIZE,
+            vertices: false,
+            midpoints: false,
+            edges: false,
+            intersections: false,
+            angles: false
+        }
+    }
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn set_snap_grid(&mut self, enabled: bool, grid_size: f64) {
+        self.snap.grid = enabled;
+        self.snap.grid_size = if grid_size > 0.0 {grid_size} else {DEFAULT_GRID_SIZE};
+        self.snap_target = None;
+        self.draw();
+    }
+
+    /// Turns one kind of object snapping on or off: "vertices", "midpoints", "edges", "intersections" or "angles".
+    pub fn set_snap_to(&mut self, target: &str, enabled: bool) {
+        match target {
+            "vertices" => self.snap.vertices = enabled,
+            "midpoints" => self.snap.midpoints = enabled,
+            "edges" => self.snap.edges = enabled,
+            "intersections" => self.snap.intersections = enabled,
+            "angles" => self.snap.angles = enabled,
+            _ => return
+        }
+        self.snap_target = None;
+        self.draw();
+    }
+}
+
+fn get_closest(candidates: Vec<PointCords>, p: PointCords, snap_distance: f64) -> Option<PointCords> {
+    candidates
+        .into_iter()
+        .map(|candidate| (get_line_length(candidate, p), candidate))
+        .filter(|(distance, _)| *distance < snap_distance)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+impl Canvas {
+    fn get_snap_vertices(&self, exclude: Option<(usize, u32)>) -> Vec<PointCords> {
+        let mut vertices: Vec<PointCords> = vec![];
+        for i in (0..self.polygons.len()).filter(|i| self.is_visible(*i)) {
+            self.polygons[i].points
+                .iter()
+                .filter(|point| exclude.is_none_or(|(_, point_id)| point.id != point_id))
+                .for_each(|point| vertices.push(PointCords(point.x, point.y)));
+        }
+        self.current_points
+            .iter()
+            .for_each(|point| vertices.push(PointCords(point.x, point.y)));
+        vertices
+    }
+
+    fn get_snap_edges(&self, exclude: Option<(usize, u32)>) -> Vec<Vec<PointCords>> {
+        let mut edges: Vec<Vec<PointCords>> = vec![];
+        for i in (0..self.polygons.len()).filter(|i| self.is_visible(*i)) {
+            let polygon = &self.polygons[i];
+            polygon.lines
+                .iter()
+                .filter(|line| exclude.is_none_or(|(_, point_id)| line.points.0 != point_id && line.points.1 != point_id))
+                .for_each(|line| {
+                    let (p1, p2) = match polygon.get_line_cords(line) {
+                        Ok(cords) => cords,
+                        Err(_) => return
+                    };
+                    match line.bezier {
+                        Some((b1, b2)) => edges.push((0..=BEZIER_SEGMENTS)
+                            .map(|k| get_bezier_point(p1, b1, b2, p2, (k as f64)/(BEZIER_SEGMENTS as f64)))
+                            .collect()),
+                        None => edges.push(vec![p1, p2])
+                    }
+                });
+        }
+        self.current_points
+            .windows(2)
+            .for_each(|pair| edges.push(vec![PointCords(pair[0].x, pair[0].y), PointCords(pair[1].x, pair[1].y)]));
+        edges
+    }
+
+    fn get_previous_vertex(&self, exclude: Option<(usize, u32)>) -> Option<PointCords> {
+        match exclude {
+            Some((polygon_id, point_id)) => self.polygons[polygon_id].lines
+                .iter()
+                .find(|line| line.points.1 == point_id)
+                .and_then(|line| self.polygons[polygon_id].get_point_by_id(line.points.0).ok()),
+            None => self.current_points
+                .last()
+                .map(|point| PointCords(point.x, point.y))
+        }
+    }
+
+    fn get_object_snap(&self, p: PointCords, exclude: Option<(usize, u32)>) -> Option<(PointCords, SnapTarget)> {
+        let snap_distance = SNAP_DISTANCE/self.viewport.scale;
+        if self.snap.vertices {
+            if let Some(snapped) = get_closest(self.get_snap_vertices(exclude), p, snap_distance) {
+                return Some((snapped, SnapTarget::Vertex));
+            }
+        }
+
+        let edges = self.get_snap_edges(exclude);
+        if self.snap.intersections {
+            // an intersection close to the cursor lies on two segments close to it
+            let segments: Vec<(usize, PointCords, PointCords)> = edges
+                .iter()
+                .enumerate()
+                .flat_map(|(i, edge)| edge.windows(2).map(move |pair| (i, pair[0], pair[1])))
+                .filter(|(_, a, b)| get_distance_to_segment(p, *a, *b) < snap_distance)
+                .collect();
+            let mut intersections: Vec<PointCords> = vec![];
+            for i in 0..segments.len() {
+                for j in (i + 1)..segments.len() {
+                    if segments[i].0 == segments[j].0 {
+                        continue;
+                    }
+                    if let Some((_, _, intersection)) = get_segments_intersection(segments[i].1, segments[i].2, segments[j].1, segments[j].2) {
+                        intersections.push(intersection);
+                    }
+                }
+            }
+            if let Some(snapped) = get_closest(intersections, p, snap_distance) {
+                return Some((snapped, SnapTarget::Intersection));
+            }
+        }
+
+        if self.snap.midpoints {
+            let midpoints: Vec<PointCords> = edges
+                .iter()
+                .map(|edge| match edge.len() {
+                    2 => calculate_middle_point(edge[0], edge[1]),
+                    _ => edge[edge.len()/2]
+                })
+                .collect();
+            if let Some(snapped) = get_closest(midpoints, p, snap_distance) {
+                return Some((snapped, SnapTarget::Midpoint));
+            }
+        }
+
+        if self.snap.edges {
+            let projections: Vec<PointCords> = edges
+                .iter()
+                .flat_map(|edge| edge.windows(2).map(|pair| get_projection_on_segment(p, pair[0], pair[1])).collect::<Vec<PointCords>>())
+                .collect();
+            if let Some(snapped) = get_closest(projections, p, snap_distance) {
+                return Some((snapped, SnapTarget::Edge));
+            }
+        }
+        None
+    }
+
+    pub fn snap_point(&mut self, x: f64, y: f64, exclude: Option<(usize, u32)>) -> PointCords {
+        let p = PointCords(x, y);
+        let previous = self.get_previous_vertex(exclude);
+        self.snap_target = self.get_object_snap(p, exclude)
+            .or_else(|| match previous {
+                Some(previous) if self.snap.angles && get_line_length(previous, p) > 0.0 => {
+                    let step = ANGLE_STEP.to_radians();
+                    let angle = ((p.1 - previous.1).atan2(p.0 - previous.0)/step).round()*step;
+                    let length = get_line_length(previous, p);
+                    Some((PointCords(previous.0 + length*angle.cos(), previous.1 + length*angle.sin()), SnapTarget::Angle))
+                },
+                _ => None
+            })
+            .or_else(|| match self.snap.grid {
+                true => {
+                    let size = self.snap.grid_size;
+                    Some((PointCords((x/size).round()*size, (y/size).round()*size), SnapTarget::Grid))
+                },
+                false => None
+            });
+        self.snap_target.map(|(snapped, _)| snapped).unwrap_or(p)
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::{BTreeMap, HashSet};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::data_models::{Line, Point, PointCords};
+use crate::error::{EditorError, Result};
+use crate::ids::IdAllocator;
+use crate::merge::is_same_point;
+use crate::polygon::Polygon;
+use crate::utils::get_line_length;
+use super::Canvas;
+
+const EPSILON: f64 = 0.000001;
+
+type LineAttributes = (bool, Option<u32>, Option<(PointCords, PointCords)>);
+
+#[derive(Clone, Copy, PartialEq)]
+struct LineRecord {
+    ends: (u32, u32),
+    attributes: LineAttributes
+}
+
+/// Vertices and edges used by several polygons have the same id in each of them and are stored once here.
+/// The copies kept by the polygons are resolved from these records: an edit changes one copy, `commit_shared`
+/// writes it to the record and from there to every other copy.
+#[derive(Clone)]
+pub struct SharedRecords {
+    points: BTreeMap<u32, PointCords>,
+    lines: BTreeMap<u32, LineRecord>
+}
+
+impl SharedRecords {
+    pub fn new() -> SharedRecords {
+        SharedRecords { points: BTreeMap::new(), lines: BTreeMap::new() }
+    }
+}
+
+impl Default for SharedRecords {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn set_shared_topology(&mut self, enabled: bool) {
+        self.save_history();
+        self.shared_topology = enabled;
+        if enabled {
+            self.weld_shared();
+        } else {
+            self.unweld_shared();
+        }
+        self.draw();
+    }
+
+    pub fn get_shared_topology(&self) -> bool {
+        self.shared_topology
+    }
+}
+
+impl Canvas {
+    /// Adds records for ids that became shared, drops those used by a single polygon again and takes over
+    /// the value of copies that all agree, e.g. after welding or moving every polygon using a vertex.
+    /// Copies that disagree are left to `commit_shared`.
+    pub fn refresh_shared(&mut self) {
+        let point_copies = self.get_shared_copies(|polygon| polygon.points.iter().map(|point| point.id).collect());
+        for (point_id, copies) in point_copies.iter() {
+            let cords: Vec<PointCords> = copies
+                .iter()
+                .map(|&(i, k)| PointCords(self.polygons[i].points[k].x, self.polygons[i].points[k].y))
+                .collect();
+            if cords.iter().all(|other| get_line_length(*other, cords[0]) < EPSILON) || !self.shared.points.contains_key(point_id) {
+                self.shared.points.insert(*point_id, cords[0]);
+            }
+        }
+        self.shared.points.retain(|point_id, _| point_copies.contains_key(point_id));
+
+        let line_copies = self.get_shared_copies(|polygon| polygon.lines.iter().map(|line| line.id).collect());
+        for (line_id, copies) in line_copies.iter() {
+            let (i, k) = copies[0];
+            let ends = self.shared.lines.get(line_id).map_or(self.polygons[i].lines[k].points, |record| record.ends);
+            let attributes: Vec<LineAttributes> = copies
+                .iter()
+                .map(|&(i, k)| get_attributes(&self.polygons[i].lines[k], ends))
+                .collect();
+            if attributes.iter().all(|other| *other == attributes[0]) || !self.shared.lines.contains_key(line_id) {
+                self.shared.lines.insert(*line_id, LineRecord { ends, attributes: attributes[0] });
+            }
+        }
+        self.shared.lines.retain(|line_id, _| line_copies.contains_key(line_id));
+    }
+
+    /// Writes the copies changed by an edit to their records and resolves the other copies from them.
+    /// A polygon whose vertex was moved this way enforces its constant lengths and relations from that vertex,
+    /// which may move further shared vertices. Every record changes at most once, so two copies edited
+    /// differently or a vertex the constraints of another polygon would move again are conflicts.
+    pub fn commit_shared(&mut self) -> Result<()> {
+        self.refresh_shared();
+        self.commit_shared_lines()?;
+        self.commit_shared_points()
+    }
+
+    fn commit_shared_lines(&mut self) -> Result<()> {
+        let line_copies = self.get_shared_copies(|polygon| polygon.lines.iter().map(|line| line.id).collect());
+        for (line_id, copies) in line_copies {
+            let record = self.shared.lines[&line_id];
+            let mut edit: Option<LineAttributes> = None;
+            for &(i, k) in copies.iter() {
+                let attributes = get_attributes(&self.polygons[i].lines[k], record.ends);
+                match edit {
+                    _ if attributes == record.attributes => {},
+                    Some(edited) if edited != attributes => return Err(EditorError::SharedConflict(line_id)),
+                    _ => edit = Some(attributes)
+                }
+            }
+            if let Some(attributes) = edit {
+                self.shared.lines.insert(line_id, LineRecord { ends: record.ends, attributes });
+                for &(i, k) in copies.iter() {
+                    set_attributes(&mut self.polygons[i].lines[k], attributes, record.ends);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn commit_shared_points(&mut self) -> Result<()> {
+        let point_copies = self.get_shared_copies(|polygon| polygon.points.iter().map(|point| point.id).collect());
+        let mut settled: HashSet<u32> = HashSet::new();
+        loop {
+            let mut moved: Vec<(usize, u32)> = vec![];
+            for (point_id, copies) in point_copies.iter() {
+                let record = self.shared.points[point_id];
+                let mut edit: Option<PointCords> = None;
+                for &(i, k) in copies.iter() {
+                    let cords = PointCords(self.polygons[i].points[k].x, self.polygons[i].points[k].y);
+                    match edit {
+                        _ if get_line_length(cords, record) < EPSILON => {},
+                        Some(edited) if get_line_length(edited, cords) >= EPSILON => return Err(EditorError::SharedConflict(*point_id)),
+                        _ => edit = Some(cords)
+                    }
+                }
+                if edit.is_some() && !settled.insert(*point_id) {
+                    return Err(EditorError::SharedConflict(*point_id));
+                }
+                let cords = edit.unwrap_or(record);
+                self.shared.points.insert(*point_id, cords);
+                for &(i, k) in copies.iter() {
+                    let point = &mut self.polygons[i].points[k];
+                    if edit.is_some() && get_line_length(PointCords(point.x, point.y), cords) >= EPSILON {
+                        moved.push((i, *point_id));
+                    }
+                    point.x = cords.0;
+                    point.y = cords.1;
+                }
+            }
+            if moved.is_empty() {
+                return Ok(());
+            }
+            for (polygon_id, point_id) in moved {
+                let result = self.correct_line_length(point_id, polygon_id, false)
+                    .and_then(|_| self.correct_line_length(point_id, polygon_id, true));
+                self.reset_visited();
+                result?;
+            }
+        }
+    }
+
+    /// Ids used by more than one polygon with their (polygon, index) positions.
+    fn get_shared_copies(&self, get_ids: impl Fn(&Polygon) -> Vec<u32>) -> BTreeMap<u32, Vec<(usize, usize)>> {
+        let mut copies: BTreeMap<u32, Vec<(usize, usize)>> = BTreeMap::new();
+        for i in 0..self.polygons.len() {
+            for (k, id) in get_ids(&self.polygons[i]).into_iter().enumerate() {
+                copies.entry(id).or_insert(vec![]).push((i, k));
+            }
+        }
+        copies.retain(|_, copies| copies.len() > 1);
+        copies
+    }
+
+    pub fn is_shared_line(&self, polygon_id: usize, line_id: u32) -> bool {
+        self.polygons
+            .iter()
+            .enumerate()
+            .any(|(i, polygon)| i != polygon_id && polygon.constains_line(line_id))
+    }
+
+    /// Gives coincident vertices of different polygons one id and unifies the edges between them.
+    pub fn weld_shared(&mut self) {
+        for j in 1..self.polygons.len() {
+            let mut welds: Vec<(u32, Point)> = vec![];
+            for point in self.polygons[j].points.iter() {
+                let target = self.polygons[..j]
+                    .iter()
+                    .flat_map(|polygon| polygon.points.iter())
+                    .find(|other| other.id != point.id && is_same_point(PointCords(other.x, other.y), PointCords(point.x, point.y)));
+                match target {
+                    Some(target) if !self.polygons[j].points.iter().any(|other| other.id == target.id)
+                        && !welds.iter().any(|(_, welded)| welded.id == target.id) => welds.push((point.id, *target)),
+                    _ => {}
+                }
+            }
+            for (old_id, point) in welds {
+                self.polygons[j].replace_point(old_id, point);
+            }
+        }
+        self.weld_lines();
+        self.refresh_shared();
+        self.recalculate();
+    }
+
+    /// Lines of different polygons between the same vertices get the id of the first one, their attributes are combined.
+    pub fn weld_lines(&mut self) {
+        for j in 1..self.polygons.len() {
+            let welds: Vec<(usize, Line)> = self.polygons[j].lines
+                .iter()
+                .enumerate()
+                .filter_map(|(k, line)| self.polygons[..j]
+                    .iter()
+                    .flat_map(|polygon| polygon.lines.iter())
+                    .find(|other| other.id != line.id && (other.points == line.points || other.points == (line.points.1, line.points.0)))
+                    .map(|other| (k, *other)))
+                .collect();
+            let mut ids: Vec<u32> = self.polygons[j].lines
+                .iter()
+                .enumerate()
+                .map(|(k, line)| welds.iter().find(|(index, _)| *index == k).map_or(line.id, |(_, other)| other.id))
+                .collect();
+            let ids_count = ids.len();
+            ids.sort();
+            ids.dedup();
+            if ids.len() != ids_count {
+                continue;
+            }
+
+            for (k, other) in welds {
+                let line = self.polygons[j].lines[k];
+                let (is_const, relation, bezier) = get_attributes(&line, line.points);
+                let (other_is_const, other_relation, other_bezier) = get_attributes(&other, line.points);
+                let relation = other_relation
+                    .or(relation)
+                    .filter(|related| *related != other.id && *related != line.id);
+                let attributes = (is_const || other_is_const, relation, other_bezier.or(bezier));
+                self.polygons[j].lines[k].id = other.id;
+                let is_gone = !self.polygons.iter().any(|polygon| polygon.constains_line(line.id));
+                self.polygons
+                    .iter_mut()
+                    .flat_map(|polygon| polygon.lines.iter_mut())
+                    .for_each(|copy| {
+                        if copy.id == other.id {
+                            set_attributes(copy, attributes, line.points);
+                        } else if is_gone && copy.relation == Some(line.id) {
+                            copy.relation = if relation == Some(copy.id) {Some(other.id)} else {None};
+                        }
+                    });
+            }
+        }
+    }
+
+    /// Gives every copy of a shared vertex or edge its own id again, copied edges lose their relations.
+    fn unweld_shared(&mut self) {
+        let mut point_ids: HashSet<u32> = HashSet::new();
+        let mut line_ids: HashSet<u32> = HashSet::new();
+        for j in 0..self.polygons.len() {
+            let copies: Vec<Point> = self.polygons[j].points
+                .iter()
+                .filter(|point| !point_ids.insert(point.id))
+                .copied()
+                .collect();
+            for point in copies {
+                self.polygons[j].replace_point(point.id, Point { id: self.ids.allocate(), ..point });
+            }
+            for k in 0..self.polygons[j].lines.len() {
+                if !line_ids.insert(self.polygons[j].lines[k].id) {
+                    self.polygons[j].lines[k].id = self.ids.allocate();
+                    self.polygons[j].lines[k].relation = None;
+                }
+            }
+        }
+        self.shared = SharedRecords::new();
+    }
+
+    /// Removes a vertex from every polygon using it.
+    pub fn remove_shared_point(&mut self, point_id: u32) -> Result<()> {
+        while let Some(i) = self.polygons.iter().position(|polygon| polygon.points.iter().any(|point| point.id == point_id)) {
+            self.remove_point(i, point_id)?;
+        }
+        self.weld_lines();
+        self.refresh_shared();
+        Ok(())
+    }
+
+    /// Splits a line in every polygon using it, the other copies get the same new vertices and lines.
+    pub fn split_shared_line<F>(&mut self, polygon_id: usize, line_id: u32, split: F) -> Result<()>
+    where F: FnOnce(&mut Polygon, &mut IdAllocator) -> Result<Vec<u32>> {
+        let (p1_id, p2_id) = self.get_polygon(polygon_id)?.get_line_by_id(line_id)?;
+        let point_ids = split(&mut self.polygons[polygon_id], &mut self.ids)?;
+
+        let polygon = &self.polygons[polygon_id];
+        let points = point_ids
+            .iter()
+            .map(|point_id| polygon.points
+                .iter()
+                .find(|point| point.id == *point_id)
+                .copied()
+                .ok_or(EditorError::PointNotFound(*point_id)))
+            .collect::<Result<Vec<Point>>>()?;
+        let chain: Vec<u32> = [p1_id].iter().chain(point_ids.iter()).chain([p2_id].iter()).copied().collect();
+        let lines = chain
+            .windows(2)
+            .map(|pair| polygon.lines
+                .iter()
+                .find(|line| line.points == (pair[0], pair[1]))
+                .copied()
+                .ok_or(EditorError::InvalidSplit(line_id)))
+            .collect::<Result<Vec<Line>>>()?;
+
+        for j in 0..self.polygons.len() {
+            if j != polygon_id && self.polygons[j].constains_line(line_id) {
+                self.polygons[j].replace_line(line_id, &points, &lines)?;
+            }
+        }
+        self.refresh_shared();
+        Ok(())
+    }
+}
+
+fn get_attributes(line: &Line, ends: (u32, u32)) -> LineAttributes {
+    let bezier = if line.points == ends {line.bezier} else {line.bezier.map(|(b1, b2)| (b2, b1))};
+    (line.is_const, line.relation, bezier)
+}
+
+fn set_attributes(line: &mut Line, (is_const, relation, bezier): LineAttributes, ends: (u32, u32)) {
+    line.is_const = is_const;
+    line.relation = relation;
+    line.bezier = if line.points == ends {bezier} else {bezier.map(|(b1, b2)| (b2, b1))};
+}
@@ -0,0 +1,27 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::units::{LengthUnit, Units};
+use super::{Canvas, State};
+
+#[wasm_bindgen]
+impl Canvas {
+    /// Sets the unit ("px", "mm", "cm" or "in") and how many canvas pixels one unit spans,
+    /// a non-positive scale uses 96 pixels per inch.
+    pub fn set_units(&mut self, unit: &str, pixels_per_unit: f64) {
+        self.units = Units::with_scale(LengthUnit::from_name(unit), pixels_per_unit);
+        if let State::Rules(Some((polygon_id, line_id))) = self.state {
+            if let Ok(line) = self.get_polygon(polygon_id).and_then(|polygon| polygon.get_line_reference_inmut(line_id)) {
+                self.length_selector.set_value(format!("{:.2}", self.units.to_units(line.length)).as_str());
+            }
+        }
+        self.draw();
+    }
+
+    pub fn get_unit(&self) -> String {
+        self.units.unit.get_name().to_string()
+    }
+
+    pub fn get_pixels_per_unit(&self) -> f64 {
+        self.units.pixels_per_unit
+    }
+}
@@ -1,54 +1,198 @@
-use crate::{data_models::{PointCords, Point}, utils::get_line_length};
-use super::Canvas;
+use crate::{data_models::{PointCords, Point}, utils::{get_line_length, calcualate_new_lines, flatten_polygon}, polygon::Polygon, triangulation::TriangleMesh, transform::{get_rotation_handle, get_scale_handle}};
+use crate::utils::check_point_hover;
+use crate::error::{EditorError, Result};
+use super::{Canvas, PressedObject, State};
+use std::rc::Rc;
+
+/// Triangulation of the scene together with the outlines it was computed from.
+pub struct MeshCache {
+    outlines: Vec<Vec<PointCords>>,
+    closed: Vec<bool>,
+    meshes: Rc<Vec<TriangleMesh>>
+}
 
 impl Canvas {
-    pub fn get_line_by_id(&self, id: u32) -> (PointCords, PointCords) {
-        for i in 0..self.polygons.len() {
-            if self.polygons[i].lines.iter().any(|line| line.id == id) {
-                let (p1, p2) = self.polygons[i].get_line_by_id(id);
-                return (self.polygons[i].get_point_by_id(p1), self.polygons[i].get_point_by_id(p2))
+    pub fn get_selected_polygon(&self) -> Option<usize> {
+        match self.state {
+            State::Rules(Some((polygon_id, _))) if polygon_id < self.polygons.len() => Some(polygon_id),
+            _ => None
+        }
+    }
+
+    pub fn get_polygon(&self, polygon_id: usize) -> Result<&Polygon> {
+        self.polygons
+            .get(polygon_id)
+            .ok_or(EditorError::PolygonNotFound(polygon_id))
+    }
+
+    pub fn get_polygon_mut(&mut self, polygon_id: usize) -> Result<&mut Polygon> {
+        self.polygons
+            .get_mut(polygon_id)
+            .ok_or(EditorError::PolygonNotFound(polygon_id))
+    }
+
+    pub fn add_polygon(&mut self, cords: Vec<PointCords>, layer: u32) -> Option<usize> {
+        if cords.len() < 3 {
+            return None;
+        }
+        let mut points: Vec<Point> = vec![];
+        cords
+            .iter()
+            .for_each(|PointCords(x, y)| {
+                points.push(Point{x: *x, y: *y, id: self.ids.allocate()});
+            });
+        let lines = calcualate_new_lines(points.iter().collect(), &mut self.ids);
+        Some(self.push_polygon(Polygon::new(points, lines, self.centroid_mode), layer))
+    }
+
+    pub fn get_holes(&self, outlines: &[Vec<PointCords>], polygon_id: usize) -> Vec<Vec<PointCords>> {
+        let is_inside = |inner: usize, outer: usize| {
+            inner != outer && outlines[inner].iter().all(|p| self.polygons[outer].contains_point(p.0, p.1))
+        };
+
+        (0..self.polygons.len())
+            .filter(|j| self.polygons[*j].is_closed())
+            .filter(|j| is_inside(*j, polygon_id))
+            .filter(|j| !(0..self.polygons.len()).any(|k| is_inside(*j, k) && is_inside(k, polygon_id)))
+            .map(|j| outlines[j].clone())
+            .collect()
+    }
+
+    /// Meshes are rebuilt only when an outline changed since the last call, so hovering and panning reuse them.
+    pub fn get_meshes(&self) -> Rc<Vec<TriangleMesh>> {
+        let outlines: Vec<Vec<PointCords>> = self.polygons
+            .iter()
+            .map(flatten_polygon)
+            .collect();
+        let closed: Vec<bool> = self.polygons
+            .iter()
+            .map(|polygon| polygon.is_closed())
+            .collect();
+        if let Some(cache) = self.mesh_cache.borrow().as_ref() {
+            if cache.outlines == outlines && cache.closed == closed {
+                return Rc::clone(&cache.meshes);
             }
         }
-        (PointCords(0.0, 0.0), PointCords(0.0, 0.0))
+        let meshes: Rc<Vec<TriangleMesh>> = Rc::new((0..self.polygons.len())
+            .map(|i| if closed[i] {
+                self.polygons[i].triangulate(&self.get_holes(&outlines, i))
+            } else {
+                TriangleMesh { vertices: vec![], triangles: vec![] }
+            })
+            .collect());
+        *self.mesh_cache.borrow_mut() = Some(MeshCache { outlines, closed, meshes: Rc::clone(&meshes) });
+        meshes
+    }
+
+    pub fn remove_polygon(&mut self, polygon_id: usize) -> Result<()> {
+        self.get_polygon(polygon_id)?;
+        for k in 0..self.polygons[polygon_id].lines.len() {
+            match self.polygons[polygon_id].lines[k].relation {
+                Some(_) if self.is_shared_line(polygon_id, self.polygons[polygon_id].lines[k].id) => {},
+                Some(line_id) => {
+                    for j in 0..self.polygons.len() {
+                        self.polygons[j].set_relation(line_id, None);
+                    }
+                },
+                None => {}
+            }
+        }
+        self.polygons.remove(polygon_id);
+        self.selection.clear();
+        Ok(())
+    }
+
+    pub fn remove_point(&mut self, polygon_id: usize, point_id: u32) -> Result<()> {
+        self.get_polygon(polygon_id)?.get_point_by_id(point_id)?;
+        if self.polygons[polygon_id].points.len() <= self.polygons[polygon_id].get_min_points_count() {
+            return self.remove_polygon(polygon_id);
+        }
+        let relation_updates = self.polygons[polygon_id].remove_point_of_id(point_id)?;
+        self.apply_relation_updates(relation_updates);
+        Ok(())
+    }
+
+    pub fn get_pivot(&self, polygon_id: usize) -> PointCords {
+        self.pivot.unwrap_or(self.polygons[polygon_id].center)
     }
 
-    pub fn enforce_relation(&mut self, line_id: u32, related_line_id: u32) {
+    pub fn get_hovered_handle(&self, x: f64, y: f64) -> Option<(usize, PressedObject)> {
+        for i in (0..self.polygons.len()).rev().filter(|i| self.is_editable(*i)) {
+            let pivot = self.get_pivot(i);
+            if check_point_hover(get_rotation_handle(self.polygons[i].center), PointCords(x, y), self.viewport.scale) {
+                return Some((i, PressedObject::RotationHandle((y - pivot.1).atan2(x - pivot.0))));
+            }
+            if check_point_hover(get_scale_handle(self.polygons[i].center), PointCords(x, y), self.viewport.scale) {
+                return Some((i, PressedObject::ScaleHandle(get_line_length(pivot, PointCords(x, y)))));
+            }
+        }
+        None
+    }
+
+    pub fn get_hovered_object(&self, x: f64, y: f64) -> Option<(usize, PressedObject)> {
+        for i in (0..self.polygons.len()).rev() {
+            if let Some(pressed_object) = self.check_polygon_hover(i, x, y) {
+                return Some((i, pressed_object));
+            }
+        }
+
+        for i in (0..self.polygons.len()).rev() {
+            if self.contains_editable_point(i, PointCords(x, y)) {
+                let center = self.polygons[i].center;
+                return Some((i, PressedObject::Inside((x - center.0, y - center.1))));
+            }
+        }
+
+        None
+    }
+
+    pub fn get_line_by_id(&self, id: u32) -> Result<(PointCords, PointCords)> {
+        let polygon = self.polygons
+            .iter()
+            .find(|polygon| polygon.constains_line(id))
+            .ok_or(EditorError::LineNotFound(id))?;
+        let (p1, p2) = polygon.get_line_by_id(id)?;
+        Ok((polygon.get_point_by_id(p1)?, polygon.get_point_by_id(p2)?))
+    }
+
+    pub fn enforce_relation(&mut self, line_id: u32, related_line_id: u32) -> Result<()> {
         for j in 0..self.polygons.len() {
             for k in 0..self.polygons[j].lines.len() {
                 if self.polygons[j].lines[k].id == related_line_id {
-                    if self.polygons[j].lines[k].visited == true {return}
+                    if self.polygons[j].lines[k].visited {return Ok(())}
                     self.polygons[j].lines[k].visited = true;
-                    let (p1, p2) = self.get_line_by_id(line_id);
+                    let (p1, p2) = self.get_line_by_id(line_id)?;
+                    if get_line_length(p1, p2) == 0.0 {
+                        return Err(EditorError::DegenerateLine(line_id));
+                    }
                     let (p3_id, p4_id) = self.polygons[j].lines[k].points;
-                    let p3 = self.polygons[j].get_point_by_id(p3_id);
+                    let p3 = self.polygons[j].get_point_by_id(p3_id)?;
                     let px = (self.polygons[j].lines[k].length * (p1.0 - p2.0))/get_line_length(p1,p2) + p3.0;
                     let py = (if p1.1 > p2.1 {1.0} else {-1.0})*(self.polygons[j].lines[k].length.powi(2) - (px - p3.0).powi(2)).sqrt() + p3.1;
 
                     for l in 0..self.polygons[j].points.len() {
                         if self.polygons[j].points[l].id == p4_id {
                             self.polygons[j].points[l] = Point{id: p4_id, x: px, y: py};
-                            self.correct_line_length(p4_id, j, true);
+                            self.correct_line_length(p4_id, j, true)?;
                         }
                     }
+                    // other copies of a shared line follow through commit_shared
+                    return Ok(());
                 }
             }
         }
+        Ok(())
     }
 
-    pub fn correct_line_mid(&mut self, extention: f64, line_id: u32, polygon_id: usize){
-        let line = self.polygons[polygon_id].get_line_reference_inmut(line_id);
-
-        let mut p0_index = 0;
-        let mut p1_index = 0;
-        for i in 0..self.polygons[polygon_id].points.len() {
-            if self.polygons[polygon_id].points[i].id == line.points.1 {
-                p0_index = i;
-            }
-            if self.polygons[polygon_id].points[i].id == line.points.0 {
-                p1_index = i;
-            }
-        }
-
+    pub fn correct_line_mid(&mut self, extention: f64, line_id: u32, polygon_id: usize) -> Result<()> {
+        let polygon = self.get_polygon(polygon_id)?;
+        let line = polygon.get_line_reference_inmut(line_id)?;
+        let get_index = |point_id: u32| polygon.points
+            .iter()
+            .position(|point| point.id == point_id)
+            .ok_or(EditorError::PointNotFound(point_id));
+        let p0_index = get_index(line.points.1)?;
+        let p1_index = get_index(line.points.0)?;
         let ratio = (self.polygons[polygon_id].points[p1_index].x - self.polygons[polygon_id].points[p0_index].x).abs()/(self.polygons[polygon_id].points[p1_index].y - self.polygons[polygon_id].points[p0_index].y).abs();
         let ratio_x = 1.0/(1.0 + 1.0/(ratio*ratio)).sqrt();
         let ratio_y = 1.0/(1.0 + ratio*ratio).sqrt();
@@ -68,28 +212,31 @@ impl Canvas {
             self.polygons[polygon_id].points[p1_index].y = self.polygons[polygon_id].points[p1_index].y - extention*ratio_y;
             self.polygons[polygon_id].points[p0_index].y = self.polygons[polygon_id].points[p0_index].y + extention*ratio_y
         }
+        Ok(())
     }
 
-    pub fn correct_line_length(&mut self, point_id: u32, polygon_id: usize, is_direction_forward: bool){
-        let mut point_index = 0;
-        while self.polygons[polygon_id].points[point_index].id != point_id {point_index = point_index + 1;}
-
-        let mut line_index = 0;
-        match is_direction_forward {
-            true => {while self.polygons[polygon_id].lines[line_index].points.0 != point_id {line_index = line_index + 1;}},
-            false => {while self.polygons[polygon_id].lines[line_index].points.1 != point_id {line_index = line_index + 1;}}
-        }
+    pub fn correct_line_length(&mut self, point_id: u32, polygon_id: usize, is_direction_forward: bool) -> Result<()> {
+        let lines_count = self.get_polygon(polygon_id)?.lines.len();
+        let is_closed = self.polygons[polygon_id].is_closed();
+        let mut line_index = match self.polygons[polygon_id].lines
+            .iter()
+            .position(|line| if is_direction_forward {line.points.0 == point_id} else {line.points.1 == point_id}) {
+            Some(line_index) => line_index,
+            // the ends of an open path have no line on one side
+            None if !is_closed => return Ok(()),
+            None => return Err(EditorError::PointNotFound(point_id))
+        };
 
         let mut x = false;
-        while (self.polygons[polygon_id].lines[line_index].is_const == true || self.polygons[polygon_id].lines[line_index].relation != None)
-        && (((if is_direction_forward {self.polygons[polygon_id].lines[line_index].points.0} else {self.polygons[polygon_id].lines[line_index].points.1} != point_id) && x == true)
-            || x == false){
+        let mut steps = 0;
+        while steps < lines_count && (self.polygons[polygon_id].lines[line_index].is_const || self.polygons[polygon_id].lines[line_index].relation.is_some())
+        && (!x || (if is_direction_forward {self.polygons[polygon_id].lines[line_index].points.0} else {self.polygons[polygon_id].lines[line_index].points.1}) != point_id){
             x = true;
-            if self.polygons[polygon_id].lines[line_index].visited == true {return}
+            steps += 1;
+            if self.polygons[polygon_id].lines[line_index].visited {return Ok(())}
             self.polygons[polygon_id].lines[line_index].visited = true;
             if self.polygons[polygon_id].lines[line_index].is_const {
-                let p1 =self.polygons[polygon_id].get_point_by_id(self.polygons[polygon_id].lines[line_index].points.0);
-                let p2 = self.polygons[polygon_id].get_point_by_id(self.polygons[polygon_id].lines[line_index].points.1);
+                let (p1, p2) = self.polygons[polygon_id].get_line_cords(&self.polygons[polygon_id].lines[line_index])?;
                 let current_len = get_line_length(p1, p2);
                 let len = self.polygons[polygon_id].lines[line_index].length;
                 let extention = len - current_len;
@@ -98,13 +245,11 @@ impl Canvas {
                 let ratio_x = 1.0/(1.0 + 1.0/(ratio*ratio)).sqrt();
                 let ratio_y = 1.0/(1.0 + ratio*ratio).sqrt();
 
-                let mut p2_index = 0;
-                for i in 0..self.polygons[polygon_id].points.len() {
-                    if self.polygons[polygon_id].points[i].id == if is_direction_forward {self.polygons[polygon_id].lines[line_index].points.1} else {self.polygons[polygon_id].lines[line_index].points.0} {
-                        p2_index = i;
-                        break;
-                    }
-                }
+                let moved_point_id = if is_direction_forward {self.polygons[polygon_id].lines[line_index].points.1} else {self.polygons[polygon_id].lines[line_index].points.0};
+                let p2_index = self.polygons[polygon_id].points
+                    .iter()
+                    .position(|point| point.id == moved_point_id)
+                    .ok_or(EditorError::PointNotFound(moved_point_id))?;
 
                 let multiplier = if is_direction_forward {1.0} else {-1.0};
                 if p2.0 > p1.0 {
@@ -120,18 +265,20 @@ impl Canvas {
                 }
             }
 
-            match self.polygons[polygon_id].lines[line_index].relation {
-                Some(line_id) => {
-                    self.enforce_relation(self.polygons[polygon_id].lines[line_index].id, line_id);
-                },
-                None => {}
+            if let Some(line_id) = self.polygons[polygon_id].lines[line_index].relation {
+                self.enforce_relation(self.polygons[polygon_id].lines[line_index].id, line_id)?;
             };
 
+            let is_last = if is_direction_forward {line_index == lines_count - 1} else {line_index == 0};
+            if is_last && !is_closed {
+                break;
+            }
             match is_direction_forward {
                 true => {line_index = if line_index == self.polygons[polygon_id].lines.len() - 1 {0} else {line_index + 1};},
                 false => {line_index = if line_index ==  0 {self.polygons[polygon_id].lines.len() - 1} else {line_index - 1};}
             }
         }
+        Ok(())
     }
 
     pub fn reset_visited(&mut self){
@@ -149,6 +296,8 @@ impl Canvas {
     }
 
     pub fn clear_current_points(&mut self) -> Vec<Point> {
+        self.shape_anchor = None;
+        self.sketch.clear();
         let mut points: Vec<Point> = vec![];
         while !self.current_points.is_empty() {
             let point = self.current_points.pop().unwrap();
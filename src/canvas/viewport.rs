@@ -0,0 +1,105 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::data_models::PointCords;
+use crate::utils::flatten_polygon;
+use super::Canvas;
+
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 50.0;
+const ZOOM_SENSITIVITY: f64 = 0.001;
+const FIT_MARGIN: f64 = 50.0;
+
+pub struct Viewport {
+    pub scale: f64,
+    pub offset: PointCords
+}
+
+impl Viewport {
+    pub fn new() -> Viewport {
+        Viewport { scale: 1.0, offset: PointCords(0.0, 0.0) }
+    }
+
+    pub fn to_world(&self, p: PointCords) -> PointCords {
+        PointCords((p.0 - self.offset.0)/self.scale, (p.1 - self.offset.1)/self.scale)
+    }
+
+    pub fn zoom_at(&mut self, screen: PointCords, factor: f64) {
+        let world = self.to_world(screen);
+        self.scale = (self.scale*factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.offset = PointCords(screen.0 - world.0*self.scale, screen.1 - world.1*self.scale);
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    pub fn on_wheel(&mut self, x: f64, y: f64, delta: f64) {
+        self.viewport.zoom_at(PointCords(x, y), (-delta*ZOOM_SENSITIVITY).exp());
+        self.draw();
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.viewport.offset = PointCords(self.viewport.offset.0 + dx, self.viewport.offset.1 + dy);
+        self.draw();
+    }
+
+    pub fn reset_view(&mut self) {
+        self.viewport = Viewport::new();
+        self.draw();
+    }
+
+    pub fn fit_to_scene(&mut self) {
+        let (width, height) = self.get_canvas_size();
+        let points: Vec<PointCords> = self.polygons
+            .iter()
+            .flat_map(flatten_polygon)
+            .collect();
+        if points.is_empty() {
+            self.reset_view();
+            return;
+        }
+
+        let min = points.iter().fold(PointCords(f64::MAX, f64::MAX), |min, p| PointCords(min.0.min(p.0), min.1.min(p.1)));
+        let max = points.iter().fold(PointCords(f64::MIN, f64::MIN), |max, p| PointCords(max.0.max(p.0), max.1.max(p.1)));
+        let scale_x = (width - 2.0*FIT_MARGIN)/(max.0 - min.0).max(1.0);
+        let scale_y = (height - 2.0*FIT_MARGIN)/(max.1 - min.1).max(1.0);
+        let scale = scale_x.min(scale_y).clamp(MIN_SCALE, MAX_SCALE);
+        let center = PointCords((min.0 + max.0)/2.0, (min.1 + max.1)/2.0);
+
+        self.viewport = Viewport {
+            scale,
+            offset: PointCords(width/2.0 - center.0*scale, height/2.0 - center.1*scale)
+        };
+        self.draw();
+    }
+
+    pub fn screen_to_world(&self, x: f64, y: f64) -> Vec<f64> {
+        let PointCords(x, y) = self.viewport.to_world(PointCords(x, y));
+        vec![x, y]
+    }
+}
+
+impl Canvas {
+    pub fn get_canvas_size(&self) -> (f64, f64) {
+        match self.context.canvas() {
+            Some(canvas) => (canvas.width() as f64, canvas.height() as f64),
+            None => (0.0, 0.0)
+        }
+    }
+
+    pub fn get_visible_area(&self) -> (PointCords, PointCords) {
+        let (width, height) = self.get_canvas_size();
+        (self.viewport.to_world(PointCords(0.0, 0.0)), self.viewport.to_world(PointCords(width, height)))
+    }
+
+    pub fn apply_viewport(&self) {
+        self.context
+            .set_transform(self.viewport.scale, 0.0, 0.0, self.viewport.scale, self.viewport.offset.0, self.viewport.offset.1)
+            .unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+use crate::data_models::PointCords;
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::utils::{cross, flatten_polygon, get_distance_to_segment, get_line_length, get_segments_intersection, get_signed_area, get_winding_number};
+
+const EPSILON: f64 = 0.0000001;
+
+impl Polygon {
+    /// Bézier edges are flattened, so the hull also covers curves bulging outwards.
+    pub fn convex_hull(&self) -> Vec<PointCords> {
+        get_convex_hull(flatten_polygon(self))
+    }
+
+    pub fn is_convex(&self) -> bool {
+        is_convex(&flatten_polygon(self))
+    }
+
+    /// Pieces are new straight-edged polygons, so curves and constraints of the outline cannot be kept.
+    pub fn convex_decomposition(&self) -> Result<Vec<Vec<PointCords>>> {
+        if self.lines.iter().any(|line| line.bezier.is_some()) {
+            return Err(EditorError::InvalidShape("convex decomposition needs straight edges"));
+        }
+        if self.lines.iter().any(|line| line.is_const || line.relation.is_some()) {
+            return Err(EditorError::InvalidShape("convex decomposition would drop constant lengths and relations"));
+        }
+        let outline = self.get_outline();
+        Ok(get_convex_decomposition(&outline)
+            .iter()
+            .map(|piece| piece.iter().map(|i| outline[*i]).collect())
+            .collect())
+    }
+}
+
+pub fn get_convex_hull(mut points: Vec<PointCords>) -> Vec<PointCords> {
+    if points.len() < 3 {
+        return points;
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    let mut lower: Vec<PointCords> = vec![];
+    for p in points.iter() {
+        while lower.len() >= 2 && cross(lower[lower.len() - 2], lower[lower.len() - 1], *p) <= 0.0 {
+            lower.pop();
+        }
+        lower.push(*p);
+    }
+
+    let mut upper: Vec<PointCords> = vec![];
+    for p in points.iter().rev() {
+        while upper.len() >= 2 && cross(upper[upper.len() - 2], upper[upper.len() - 1], *p) <= 0.0 {
+            upper.pop();
+        }
+        upper.push(*p);
+    }
+
+    lower.pop();
+    upper.pop();
+    lower.append(&mut upper);
+    lower
+}
+
+pub fn is_convex(outline: &[PointCords]) -> bool {
+    if outline.len() < 3 {
+        return false;
+    }
+    let mut sign = 0.0;
+    let mut turning = 0.0;
+    for i in 0..outline.len() {
+        let a = outline[i];
+        let b = outline[(i + 1) % outline.len()];
+        let c = outline[(i + 2) % outline.len()];
+        let turn = cross(a, b, c);
+        if turn.abs() > EPSILON {
+            if sign * turn < 0.0 {
+                return false;
+            }
+            sign = turn;
+        }
+        let angle1 = (b.1 - a.1).atan2(b.0 - a.0);
+        let angle2 = (c.1 - b.1).atan2(c.0 - b.0);
+        let mut difference = angle2 - angle1;
+        while difference > std::f64::consts::PI {difference -= 2.0*std::f64::consts::PI;}
+        while difference < -std::f64::consts::PI {difference += 2.0*std::f64::consts::PI;}
+        turning += difference;
+    }
+    sign != 0.0 && (turning.abs() - 2.0*std::f64::consts::PI).abs() < 0.001
+}
+
+fn find_shared_edge(a: &[usize], b: &[usize]) -> Option<(usize, usize)> {
+    for i in 0..a.len() {
+        let u = a[i];
+        let v = a[(i + 1) % a.len()];
+        for j in 0..b.len() {
+            if b[j] == v && b[(j + 1) % b.len()] == u {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+fn merge_pieces(a: &[usize], b: &[usize], i: usize, j: usize) -> Vec<usize> {
+    let mut merged = vec![];
+    for k in 0..a.len() {
+        merged.push(a[(i + 1 + k) % a.len()]);
+    }
+    merged.pop();
+    for k in 0..b.len() - 1 {
+        merged.push(b[(j + 1 + k) % b.len()]);
+    }
+    merged
+}
+
+/// A diagonal between two vertices of a counterclockwise piece lies inside it and touches no other vertex or edge.
+fn is_diagonal(outline: &[PointCords], piece: &[usize], i: usize, j: usize) -> bool {
+    let (a, b) = (outline[piece[i]], outline[piece[j]]);
+    if get_line_length(a, b) < EPSILON {
+        return false;
+    }
+    for k in 0..piece.len() {
+        let (c, d) = (outline[piece[k]], outline[piece[(k + 1) % piece.len()]]);
+        if k != i && k != j && get_distance_to_segment(c, a, b) < EPSILON {
+            return false;
+        }
+        if get_segments_intersection(a, b, c, d).is_some() {
+            return false;
+        }
+    }
+    let ring: Vec<PointCords> = piece.iter().map(|k| outline[*k]).collect();
+    get_winding_number(&ring, PointCords((a.0 + b.0)/2.0, (a.1 + b.1)/2.0)) != 0
+}
+
+/// Splits the piece at its reflex vertices, preferring diagonals that leave both new corners convex.
+fn split_at_reflex(outline: &[PointCords], piece: Vec<usize>, pieces: &mut Vec<Vec<usize>>) {
+    let n = piece.len();
+    let reflex = (0..n).find(|i| cross(outline[piece[(i + n - 1) % n]], outline[piece[*i]], outline[piece[(i + 1) % n]]) < -EPSILON);
+    let i = match reflex {
+        Some(i) => i,
+        None => {
+            pieces.push(piece);
+            return;
+        }
+    };
+    let (prev, vertex, next) = (outline[piece[(i + n - 1) % n]], outline[piece[i]], outline[piece[(i + 1) % n]]);
+    let diagonal = (0..n)
+        .filter(|j| *j != i && *j != (i + 1) % n && *j != (i + n - 1) % n)
+        .filter(|j| is_diagonal(outline, &piece, i, *j))
+        .min_by(|a, b| {
+            let resolves = |j: usize| cross(outline[piece[j]], vertex, next) >= -EPSILON && cross(prev, vertex, outline[piece[j]]) >= -EPSILON;
+            let length = |j: usize| get_line_length(vertex, outline[piece[j]]);
+            resolves(*b).cmp(&resolves(*a)).then(length(*a).total_cmp(&length(*b)))
+        });
+    let j = match diagonal {
+        Some(j) => j,
+        None => {
+            pieces.push(piece);
+            return;
+        }
+    };
+    let first: Vec<usize> = (0..=((j + n - i) % n)).map(|k| piece[(i + k) % n]).collect();
+    let second: Vec<usize> = (0..=((i + n - j) % n)).map(|k| piece[(j + k) % n]).collect();
+    split_at_reflex(outline, first, pieces);
+    split_at_reflex(outline, second, pieces);
+}
+
+/// Splits the outline along diagonals and then removes every diagonal whose neighbouring pieces stay convex
+/// without it (Hertel-Mehlhorn). Pieces are counterclockwise lists of outline indices.
+pub fn get_convex_decomposition(outline: &[PointCords]) -> Vec<Vec<usize>> {
+    if outline.len() < 3 {
+        return vec![];
+    }
+    let mut indices: Vec<usize> = (0..outline.len()).collect();
+    if get_signed_area(outline) < 0.0 {
+        indices.reverse();
+    }
+    let mut pieces: Vec<Vec<usize>> = vec![];
+    split_at_reflex(outline, indices, &mut pieces);
+
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        'search: for a in 0..pieces.len() {
+            for b in (a + 1)..pieces.len() {
+                if let Some((i, j)) = find_shared_edge(&pieces[a], &pieces[b]) {
+                    let merged = merge_pieces(&pieces[a], &pieces[b], i, j);
+                    let merged_outline: Vec<PointCords> = merged.iter().map(|k| outline[*k]).collect();
+                    if is_convex(&merged_outline) && get_signed_area(&merged_outline) > 0.0 {
+                        pieces[a] = merged;
+                        pieces.remove(b);
+                        merged_any = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(outline: &[PointCords]) -> f64 {
+        get_signed_area(outline).abs()
+    }
+
+    #[test]
+    fn hull_skips_inner_and_collinear_points() {
+        let points = vec![
+            PointCords(0.0, 0.0), PointCords(2.0, 0.0), PointCords(4.0, 0.0), PointCords(4.0, 4.0),
+            PointCords(1.0, 1.0), PointCords(0.0, 4.0), PointCords(2.0, 3.0)
+        ];
+        let hull = get_convex_hull(points);
+        assert_eq!(hull.len(), 4);
+        assert!(is_convex(&hull));
+        assert!((area(&hull) - 16.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn convexity_of_both_orientations() {
+        let square = vec![PointCords(0.0, 0.0), PointCords(1.0, 0.0), PointCords(1.0, 1.0), PointCords(0.0, 1.0)];
+        let mut reversed = square.clone();
+        reversed.reverse();
+        assert!(is_convex(&square));
+        assert!(is_convex(&reversed));
+
+        let arrow = vec![PointCords(0.0, 0.0), PointCords(2.0, 1.0), PointCords(4.0, 0.0), PointCords(2.0, 4.0)];
+        assert!(!is_convex(&arrow));
+        // a pentagram turns twice around its center
+        let star: Vec<PointCords> = (0..5)
+            .map(|i| {
+                let angle = (i as f64)*4.0*std::f64::consts::PI/5.0;
+                PointCords(angle.cos(), angle.sin())
+            })
+            .collect();
+        assert!(!is_convex(&star));
+    }
+
+    #[test]
+    fn decomposition_covers_outline_with_convex_pieces() {
+        let comb = vec![
+            PointCords(0.0, 0.0), PointCords(10.0, 0.0), PointCords(10.0, 10.0), PointCords(8.0, 10.0),
+            PointCords(8.0, 4.0), PointCords(6.0, 4.0), PointCords(6.0, 10.0), PointCords(4.0, 10.0),
+            PointCords(4.0, 4.0), PointCords(2.0, 4.0), PointCords(2.0, 10.0), PointCords(0.0, 10.0)
+        ];
+        for outline in [comb.clone(), comb.iter().rev().copied().collect()].iter() {
+            let pieces = get_convex_decomposition(outline);
+            let outlines: Vec<Vec<PointCords>> = pieces
+                .iter()
+                .map(|piece| piece.iter().map(|i| outline[*i]).collect())
+                .collect();
+            assert!(outlines.iter().all(|piece| is_convex(piece)));
+            let total: f64 = outlines.iter().map(|piece| area(piece)).sum();
+            assert!((total - area(outline)).abs() < EPSILON);
+            // four reflex vertices need at most four diagonals
+            assert!(pieces.len() >= 3 && pieces.len() <= 5, "{} pieces", pieces.len());
+        }
+    }
+
+    #[test]
+    fn convex_outline_stays_whole() {
+        let square = vec![PointCords(0.0, 0.0), PointCords(1.0, 0.0), PointCords(1.0, 1.0), PointCords(0.0, 1.0)];
+        assert_eq!(get_convex_decomposition(&square), vec![vec![0, 1, 2, 3]]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::f64::consts::PI;
+
+use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::ids::IdAllocator;
+use crate::polygon::Polygon;
+use crate::utils::get_line_length;
+
+const EPSILON: f64 = 0.000001;
+
+impl Polygon {
+    /// Replaces a vertex with two points on its edges joined by a new line: a Bézier arc of the given radius
+    /// tangent to both edges (fillet) or a straight cut at the given distance from the vertex (chamfer).
+    /// Both edges keep their ids and relations, only their lengths change, so edges of constant length
+    /// cannot be shortened.
+    /// Returns the id of the new line.
+    pub fn round_corner(&mut self, point_id: u32, style: CornerStyle, size: f64, ids: &mut IdAllocator) -> Result<u32> {
+        let point_index = self.points
+            .iter()
+            .position(|point| point.id == point_id)
+            .ok_or(EditorError::PointNotFound(point_id))?;
+        let incoming_index = self.lines
+            .iter()
+            .position(|line| line.points.1 == point_id)
+            .ok_or(EditorError::InvalidCorner(point_id))?;
+        let outgoing_index = self.lines
+            .iter()
+            .position(|line| line.points.0 == point_id)
+            .ok_or(EditorError::InvalidCorner(point_id))?;
+        let incoming = self.lines[incoming_index];
+        let outgoing = self.lines[outgoing_index];
+        if size.is_nan() || size <= 0.0 || incoming.bezier.is_some() || outgoing.bezier.is_some() {
+            return Err(EditorError::InvalidCorner(point_id));
+        }
+        if incoming.is_const || outgoing.is_const {
+            return Err(EditorError::InvalidCorner(point_id));
+        }
+        let prev = self.get_point_by_id(incoming.points.0)?;
+        let vertex = self.get_point_by_id(point_id)?;
+        let next = self.get_point_by_id(outgoing.points.1)?;
+
+        let (prev_length, next_length) = (get_line_length(vertex, prev), get_line_length(vertex, next));
+        if prev_length < EPSILON || next_length < EPSILON {
+            return Err(EditorError::InvalidCorner(point_id));
+        }
+        let u1 = PointCords((prev.0 - vertex.0)/prev_length, (prev.1 - vertex.1)/prev_length);
+        let u2 = PointCords((next.0 - vertex.0)/next_length, (next.1 - vertex.1)/next_length);
+        let angle = (u1.0*u2.0 + u1.1*u2.1).clamp(-1.0, 1.0).acos();
+        // there is no corner between collinear edges
+        if !(EPSILON..=PI - EPSILON).contains(&angle) {
+            return Err(EditorError::InvalidCorner(point_id));
+        }
+        let distance = match style {
+            CornerStyle::Fillet => size/(angle/2.0).tan(),
+            CornerStyle::Chamfer => size
+        };
+        if distance >= prev_length - EPSILON || distance >= next_length - EPSILON {
+            return Err(EditorError::InvalidCorner(point_id));
+        }
+
+        let t1 = PointCords(vertex.0 + distance*u1.0, vertex.1 + distance*u1.1);
+        let t2 = PointCords(vertex.0 + distance*u2.0, vertex.1 + distance*u2.1);
+        let bezier = match style {
+            // a cubic arc sweeping the turn between the edges, its control points lie on the edges
+            CornerStyle::Fillet => {
+                let k = 4.0/3.0*((PI - angle)/4.0).tan()*size;
+                Some((PointCords(t1.0 - k*u1.0, t1.1 - k*u1.1), PointCords(t2.0 - k*u2.0, t2.1 - k*u2.1)))
+            },
+            CornerStyle::Chamfer => None
+        };
+        let (t1_id, t2_id) = (ids.allocate(), ids.allocate());
+        self.points[point_index] = Point { x: t1.0, y: t1.1, id: t1_id };
+        self.points.insert(point_index + 1, Point { x: t2.0, y: t2.1, id: t2_id });
+        self.lines[incoming_index] = Line {
+            points: (incoming.points.0, t1_id),
+            length: get_line_length(prev, t1),
+            ..incoming
+        };
+        self.lines[outgoing_index] = Line {
+            points: (t2_id, outgoing.points.1),
+            length: get_line_length(t2, next),
+            ..outgoing
+        };
+        let line_id = ids.allocate();
+        self.lines.insert(outgoing_index, Line {
+            points: (t1_id, t2_id),
+            length: get_line_length(t1, t2),
+            id: line_id,
+            is_const: false,
+            relation: None,
+            visited: false,
+            bezier
+        });
+        self.update_center();
+        Ok(line_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::calcualate_new_lines;
+
+    fn square(ids: &mut IdAllocator) -> Polygon {
+        let points: Vec<Point> = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .iter()
+            .map(|(x, y)| Point { x: *x, y: *y, id: ids.allocate() })
+            .collect();
+        let lines = calcualate_new_lines(points.iter().collect(), ids);
+        Polygon::new(points, lines, CentroidMode::VertexMean)
+    }
+
+    #[test]
+    fn chamfer_cuts_both_edges() {
+        let mut ids = IdAllocator::new();
+        let mut polygon = square(&mut ids);
+        let corner = polygon.points[1].id;
+        let line_id = polygon.round_corner(corner, CornerStyle::Chamfer, 2.0, &mut ids).unwrap();
+        assert_eq!(polygon.points.len(), 5);
+        let line = polygon.get_line_reference_inmut(line_id).unwrap();
+        assert!((line.length - 8.0_f64.sqrt()).abs() < EPSILON);
+        assert!(polygon.get_point_by_id(corner).is_err());
+    }
+
+    #[test]
+    fn fillet_is_tangent_arc() {
+        let mut ids = IdAllocator::new();
+        let mut polygon = square(&mut ids);
+        let corner = polygon.points[2].id;
+        let line_id = polygon.round_corner(corner, CornerStyle::Fillet, 3.0, &mut ids).unwrap();
+        let line = *polygon.get_line_reference_inmut(line_id).unwrap();
+        let (t1, t2) = polygon.get_line_cords(&line).unwrap();
+        // a right angle puts the tangent points at the radius from the corner
+        assert!(get_line_length(t1, PointCords(10.0, 7.0)) < EPSILON);
+        assert!(get_line_length(t2, PointCords(7.0, 10.0)) < EPSILON);
+        assert!(line.bezier.is_some());
+    }
+
+    #[test]
+    fn constant_edges_and_bad_sizes_are_rejected() {
+        let mut ids = IdAllocator::new();
+        let mut polygon = square(&mut ids);
+        let corner = polygon.points[1].id;
+        for size in [0.0, -1.0, f64::NAN, 20.0] {
+            assert_eq!(polygon.round_corner(corner, CornerStyle::Chamfer, size, &mut ids), Err(EditorError::InvalidCorner(corner)));
+        }
+        polygon.lines
+            .iter_mut()
+            .filter(|line| line.points.1 == corner)
+            .for_each(|line| line.is_const = true);
+        assert_eq!(polygon.round_corner(corner, CornerStyle::Fillet, 1.0, &mut ids), Err(EditorError::InvalidCorner(corner)));
+        assert_eq!(polygon.points.len(), 4);
+    }
+}
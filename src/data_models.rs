@@ -16,5 +16,57 @@ pub struct Point{
     pub id: u32
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct PointCords(pub f64, pub f64);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CentroidMode {
+    Area,
+    VertexMean
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum JoinStyle {
+    Miter,
+    Round,
+    Bevel
+}
+
+/// A named group of polygons, layers are drawn in the order of the layer list.
+/// Hidden and locked layers cannot be edited.
+#[derive(Clone)]
+pub struct Layer {
+    pub id: u32,
+    pub name: String,
+    pub visible: bool,
+    pub locked: bool
+}
+
+/// A dimension annotation of a polygon: the length of a line or the angle at a vertex, given by id.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dimension {
+    Length(u32),
+    Angle(u32)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CornerStyle {
+    Fillet,
+    Chamfer
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SimplifyMethod {
+    DouglasPeucker,
+    Visvalingam
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShapeTool {
+    Polygon,
+    RegularPolygon(u32),
+    Rectangle,
+    Ellipse,
+    Circle,
+    Freehand
+}
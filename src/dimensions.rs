@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+
+use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::units::Units;
+use crate::utils::{calculate_middle_point, get_line_length, get_signed_area};
+
+const DIMENSION_OFFSET: f64 = 25.0;
+const EXTENSION_GAP: f64 = 5.0;
+const EXTENSION_OVERSHOOT: f64 = 5.0;
+const ANGLE_RADIUS: f64 = 25.0;
+const ARROW_SIZE: f64 = 8.0;
+const ARROW_ANGLE: f64 = PI/8.0;
+const LABEL_OFFSET: f64 = 12.0;
+const EPSILON: f64 = 0.000001;
+
+/// Geometry of a dimension annotation shared by the canvas and the SVG export: straight segments
+/// (extension lines, dimension line and arrowheads), an optional arc (center, radius, start angle, sweep)
+/// and the label with its position.
+pub struct DimensionLayout {
+    pub segments: Vec<(PointCords, PointCords)>,
+    pub arc: Option<(PointCords, f64, f64, f64)>,
+    pub label: PointCords,
+    pub text: String
+}
+
+impl Polygon {
+    /// Adds the dimension or removes it when the polygon already shows it.
+    pub fn toggle_dimension(&mut self, dimension: Dimension) -> Result<()> {
+        match dimension {
+            Dimension::Length(line_id) => {self.get_line_by_id(line_id)?;},
+            Dimension::Angle(point_id) => {self.get_point_by_id(point_id)?;}
+        }
+        match self.dimensions.iter().position(|other| *other == dimension) {
+            Some(position) => {self.dimensions.remove(position);},
+            None => self.dimensions.push(dimension)
+        }
+        Ok(())
+    }
+
+    /// Dimensions whose line or vertex was removed by later edits are skipped.
+    pub fn get_dimension_layouts(&self, units: &Units) -> Vec<DimensionLayout> {
+        self.dimensions
+            .iter()
+            .filter_map(|dimension| match dimension {
+                Dimension::Length(line_id) => self.get_length_layout(*line_id, units).ok(),
+                Dimension::Angle(point_id) => self.get_angle_layout(*point_id).ok()
+            })
+            .collect()
+    }
+
+    pub fn is_valid_dimension(&self, dimension: &Dimension) -> bool {
+        match dimension {
+            Dimension::Length(line_id) => self.constains_line(*line_id),
+            Dimension::Angle(point_id) => self.points.iter().any(|point| point.id == *point_id)
+        }
+    }
+
+    /// Linear dimension of the chord of a line, drawn on the outer side of a closed polygon.
+    fn get_length_layout(&self, line_id: u32, units: &Units) -> Result<DimensionLayout> {
+        let line = *self.get_line_reference_inmut(line_id)?;
+        let (p1, p2) = self.get_line_cords(&line)?;
+        let length = get_line_length(p1, p2);
+        if length < EPSILON {
+            return Err(EditorError::DegenerateLine(line_id));
+        }
+        let direction = PointCords((p2.0 - p1.0)/length, (p2.1 - p1.1)/length);
+        let normal = if self.is_closed() && get_signed_area(&self.get_outline()) < 0.0 {
+            PointCords(-direction.1, direction.0)
+        } else {
+            PointCords(direction.1, -direction.0)
+        };
+        let offset = |p: PointCords, distance: f64| PointCords(p.0 + distance*normal.0, p.1 + distance*normal.1);
+        let (a, b) = (offset(p1, DIMENSION_OFFSET), offset(p2, DIMENSION_OFFSET));
+
+        let mut segments = vec![
+            (offset(p1, EXTENSION_GAP), offset(p1, DIMENSION_OFFSET + EXTENSION_OVERSHOOT)),
+            (offset(p2, EXTENSION_GAP), offset(p2, DIMENSION_OFFSET + EXTENSION_OVERSHOOT)),
+            (a, b)
+        ];
+        segments.extend(get_arrow(a, PointCords(-direction.0, -direction.1)));
+        segments.extend(get_arrow(b, direction));
+        Ok(DimensionLayout {
+            segments,
+            arc: None,
+            label: offset(calculate_middle_point(a, b), LABEL_OFFSET),
+            text: units.format_length(length)
+        })
+    }
+
+    /// Angle dimension inside a closed polygon, an open path shows the angle smaller than 180°.
+    fn get_angle_layout(&self, point_id: u32) -> Result<DimensionLayout> {
+        let incoming = self.lines
+            .iter()
+            .find(|line| line.points.1 == point_id)
+            .ok_or(EditorError::PointNotFound(point_id))?;
+        let outgoing = self.lines
+            .iter()
+            .find(|line| line.points.0 == point_id)
+            .ok_or(EditorError::PointNotFound(point_id))?;
+        let prev = self.get_point_by_id(incoming.points.0)?;
+        let vertex = self.get_point_by_id(point_id)?;
+        let next = self.get_point_by_id(outgoing.points.1)?;
+        let to_prev = (prev.1 - vertex.1).atan2(prev.0 - vertex.0);
+        let to_next = (next.1 - vertex.1).atan2(next.0 - vertex.0);
+
+        let counterclockwise = (to_prev - to_next).rem_euclid(2.0*PI);
+        let (start, sweep) = if self.is_closed() {
+            if get_signed_area(&self.get_outline()) >= 0.0 {
+                (to_next, counterclockwise)
+            } else {
+                (to_prev, 2.0*PI - counterclockwise)
+            }
+        } else if counterclockwise <= PI {
+            (to_next, counterclockwise)
+        } else {
+            (to_prev, 2.0*PI - counterclockwise)
+        };
+        if sweep < EPSILON {
+            return Err(EditorError::PointNotFound(point_id));
+        }
+
+        let on_arc = |angle: f64, radius: f64| PointCords(vertex.0 + radius*angle.cos(), vertex.1 + radius*angle.sin());
+        let end = start + sweep;
+        let mut segments: Vec<(PointCords, PointCords)> = vec![];
+        segments.extend(get_arrow(on_arc(start, ANGLE_RADIUS), PointCords(start.sin(), -start.cos())));
+        segments.extend(get_arrow(on_arc(end, ANGLE_RADIUS), PointCords(-end.sin(), end.cos())));
+        Ok(DimensionLayout {
+            segments,
+            arc: Some((vertex, ANGLE_RADIUS, start, sweep)),
+            label: on_arc(start + sweep/2.0, ANGLE_RADIUS + LABEL_OFFSET),
+            text: format!("{:.1}°", sweep.to_degrees())
+        })
+    }
+}
+
+/// Two strokes of an arrowhead with its tip at the given point, pointing in the given unit direction.
+fn get_arrow(tip: PointCords, direction: PointCords) -> Vec<(PointCords, PointCords)> {
+    [ARROW_ANGLE, -ARROW_ANGLE]
+        .iter()
+        .map(|angle| {
+            let (sin, cos) = angle.sin_cos();
+            let back = PointCords(-(direction.0*cos - direction.1*sin), -(direction.0*sin + direction.1*cos));
+            (tip, PointCords(tip.0 + ARROW_SIZE*back.0, tip.1 + ARROW_SIZE*back.1))
+        })
+        .collect()
+}
@@ -1,28 +1,43 @@
-use crate::{polygon::Polygon, data_models::PointCords};
+use crate::{polygon::Polygon, data_models::PointCords, triangulation::TriangleMesh};
 use web_sys::CanvasRenderingContext2d;
 use wasm_bindgen::JsValue;
 use js_sys::Array;
 use crate::utils::calculate_middle_point;
+use crate::transform::{get_rotation_handle, get_scale_handle};
+use crate::canvas::snapping::SnapTarget;
+use crate::dimensions::DimensionLayout;
+use crate::units::Units;
 use std::collections::HashMap;
 
-const CANVAS_X: f64 = 100000.0;
-const CANVAS_Y: f64 = 70000.0;
 pub const POINT_RADIUS: f64 = 5.0;
 pub const CENTER_RADIUS: f64 = 7.0;
 const HL_RADIUS: f64 = 8.0;
 pub const BASIC_COLOR: &str = "rgb(44, 0, 117)";
 pub const HIGHLIGHT_COLOR: &str = "rgb(207, 52, 121)";
 pub const CONSTANT_COLOR: &str = "rgb(141, 55, 179)";
+pub const WIREFRAME_COLOR: &str = "rgb(160, 160, 160)";
+pub const HANDLE_COLOR: &str = "rgb(0, 128, 128)";
+pub const GRID_COLOR: &str = "rgb(225, 225, 235)";
+pub const SNAP_COLOR: &str = "rgb(230, 120, 0)";
+pub const DIMENSION_COLOR: &str = "rgb(0, 110, 60)";
+const HANDLE_SIZE: f64 = 10.0;
+const PIVOT_SIZE: f64 = 10.0;
+const SNAP_SIZE: f64 = 7.0;
 
 impl Polygon{
-    pub fn draw(&self, context: &CanvasRenderingContext2d, mut relation_number: u32, relation_map: &mut HashMap<u32, u32> ){
+    pub fn draw(&self, context: &CanvasRenderingContext2d, mut relation_number: u32, relation_map: &mut HashMap<u32, u32>, wireframe: Option<&TriangleMesh>, units: &Units){
+        if let Some(mesh) = wireframe {
+            draw_wireframe(context, mesh);
+        }
         context.set_line_width(3.0);
         self.lines
             .iter()
             .for_each(|line| {
                 context.begin_path();
-                let p1 = self.get_point_by_id(line.points.0);
-                let p2 = self.get_point_by_id(line.points.1);
+                let (p1, p2) = match self.get_line_cords(line) {
+                    Ok(cords) => cords,
+                    Err(_) => return
+                };
                 match line.bezier {
                     None => {
                         let mid = calculate_middle_point(p1, p2);
@@ -75,11 +90,160 @@ impl Polygon{
             });
 
         draw_point(context, self.center, CENTER_RADIUS);
+
+        self.get_dimension_layouts(units)
+            .iter()
+            .for_each(|layout| draw_dimension(context, layout));
     }
 }
 
+pub fn draw_dimension(context: &CanvasRenderingContext2d, layout: &DimensionLayout){
+    context.begin_path();
+    context.set_line_width(1.0);
+    context.set_stroke_style(&JsValue::from_str(DIMENSION_COLOR));
+    layout.segments
+        .iter()
+        .for_each(|(p1, p2)| {
+            context.move_to(p1.0, p1.1);
+            context.line_to(p2.0, p2.1);
+        });
+    if let Some((center, radius, start, sweep)) = layout.arc {
+        context.move_to(center.0 + radius*start.cos(), center.1 + radius*start.sin());
+        context.arc(center.0, center.1, radius, start, start + sweep).unwrap();
+    }
+    context.stroke();
+    context.set_fill_style(&JsValue::from_str(DIMENSION_COLOR));
+    context.set_font("14px sans-serif");
+    context.set_text_align("center");
+    context.set_text_baseline("middle");
+    context.fill_text(layout.text.as_str(), layout.label.0, layout.label.1).unwrap();
+    context.set_text_align("start");
+    context.set_text_baseline("alphabetic");
+    context.set_fill_style(&JsValue::from_str(BASIC_COLOR));
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
+pub fn draw_wireframe(context: &CanvasRenderingContext2d, mesh: &TriangleMesh){
+    context.begin_path();
+    context.set_line_width(1.0);
+    mesh.triangles
+        .iter()
+        .for_each(|(a, b, c)| {
+            let (p1, p2, p3) = (mesh.vertices[*a], mesh.vertices[*b], mesh.vertices[*c]);
+            context.move_to(p1.0, p1.1);
+            context.line_to(p2.0, p2.1);
+            context.line_to(p3.0, p3.1);
+            context.line_to(p1.0, p1.1);
+        });
+    context.set_stroke_style(&JsValue::from_str(WIREFRAME_COLOR));
+    context.stroke();
+    context.begin_path();
+}
+
+pub fn draw_transform_handles(context: &CanvasRenderingContext2d, center: PointCords){
+    let rotation = get_rotation_handle(center);
+    let scale = get_scale_handle(center);
+    context.begin_path();
+    context.set_line_width(1.0);
+    context.set_stroke_style(&JsValue::from_str(HANDLE_COLOR));
+    context.move_to(center.0, center.1);
+    context.line_to(rotation.0, rotation.1);
+    context.move_to(center.0, center.1);
+    context.line_to(scale.0, scale.1);
+    context.stroke();
+    context.begin_path();
+    context.arc(rotation.0, rotation.1, HANDLE_SIZE/2.0, 0.0, 2.0*std::f64::consts::PI).unwrap();
+    context.rect(scale.0 - HANDLE_SIZE/2.0, scale.1 - HANDLE_SIZE/2.0, HANDLE_SIZE, HANDLE_SIZE);
+    context.stroke();
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
+pub fn draw_pivot(context: &CanvasRenderingContext2d, pivot: PointCords){
+    context.begin_path();
+    context.set_line_width(2.0);
+    context.set_stroke_style(&JsValue::from_str(HANDLE_COLOR));
+    context.move_to(pivot.0 - PIVOT_SIZE, pivot.1);
+    context.line_to(pivot.0 + PIVOT_SIZE, pivot.1);
+    context.move_to(pivot.0, pivot.1 - PIVOT_SIZE);
+    context.line_to(pivot.0, pivot.1 + PIVOT_SIZE);
+    context.stroke();
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
+pub fn draw_grid(context: &CanvasRenderingContext2d, size: f64, a: PointCords, b: PointCords){
+    context.begin_path();
+    context.set_line_width(1.0);
+    context.set_stroke_style(&JsValue::from_str(GRID_COLOR));
+    let mut x = (a.0/size).floor()*size;
+    while x <= b.0 {
+        context.move_to(x, a.1);
+        context.line_to(x, b.1);
+        x += size;
+    }
+    let mut y = (a.1/size).floor()*size;
+    while y <= b.1 {
+        context.move_to(a.0, y);
+        context.line_to(b.0, y);
+        y += size;
+    }
+    context.stroke();
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
+pub fn draw_snap_indicator(context: &CanvasRenderingContext2d, p: PointCords, target: SnapTarget){
+    context.begin_path();
+    context.set_line_width(2.0);
+    context.set_stroke_style(&JsValue::from_str(SNAP_COLOR));
+    match target {
+        SnapTarget::Vertex | SnapTarget::Grid => context.rect(p.0 - SNAP_SIZE, p.1 - SNAP_SIZE, 2.0*SNAP_SIZE, 2.0*SNAP_SIZE),
+        SnapTarget::Midpoint => {
+            context.move_to(p.0, p.1 - SNAP_SIZE);
+            context.line_to(p.0 + SNAP_SIZE, p.1 + SNAP_SIZE);
+            context.line_to(p.0 - SNAP_SIZE, p.1 + SNAP_SIZE);
+            context.close_path();
+        },
+        SnapTarget::Intersection => {
+            context.move_to(p.0 - SNAP_SIZE, p.1 - SNAP_SIZE);
+            context.line_to(p.0 + SNAP_SIZE, p.1 + SNAP_SIZE);
+            context.move_to(p.0 + SNAP_SIZE, p.1 - SNAP_SIZE);
+            context.line_to(p.0 - SNAP_SIZE, p.1 + SNAP_SIZE);
+        },
+        SnapTarget::Edge | SnapTarget::Angle => {
+            context.arc(p.0, p.1, SNAP_SIZE, 0.0, 2.0*std::f64::consts::PI).unwrap();
+        }
+    }
+    context.stroke();
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
+pub fn draw_selection_rect(context: &CanvasRenderingContext2d, a: PointCords, b: PointCords){
+    context.begin_path();
+    context.set_line_width(1.0);
+    context.set_stroke_style(&JsValue::from_str(HIGHLIGHT_COLOR));
+    context.set_line_dash(&JsValue::from(vec![&JsValue::from_f64(5.0),&JsValue::from_f64(5.0)].into_iter().collect::<Array>())).unwrap();
+    context.stroke_rect(a.0.min(b.0), a.1.min(b.1), (a.0 - b.0).abs(), (a.1 - b.1).abs());
+    let v: Vec<&JsValue> = vec![];
+    context.set_line_dash(&JsValue::from(v.into_iter().collect::<Array>())).unwrap();
+    context.set_line_width(3.0);
+    context.set_stroke_style(&JsValue::from_str(BASIC_COLOR));
+    context.begin_path();
+}
+
 pub fn clear_canvas(context: &CanvasRenderingContext2d){
-    context.clear_rect(0.0,0.0,CANVAS_X,CANVAS_Y);
+    context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+    if let Some(canvas) = context.canvas() {
+        context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    }
     context.stroke();
     context.begin_path();
 }
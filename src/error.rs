@@ -0,0 +1,47 @@
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorError {
+    PointNotFound(u32),
+    LineNotFound(u32),
+    PolygonNotFound(usize),
+    DegenerateLine(u32),
+    InvalidSplit(u32),
+    InvalidCorner(u32),
+    InvalidLayer(u32),
+    InvalidMerge(&'static str),
+    SharedConflict(u32),
+    InvalidShape(&'static str),
+    InvalidOffset(&'static str),
+    InvalidScene(&'static str)
+}
+
+pub type Result<T> = std::result::Result<T, EditorError>;
+
+impl fmt::Display for EditorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditorError::PointNotFound(id) => write!(f, "point {} does not exist", id),
+            EditorError::LineNotFound(id) => write!(f, "line {} does not exist", id),
+            EditorError::PolygonNotFound(id) => write!(f, "polygon {} does not exist", id),
+            EditorError::DegenerateLine(id) => write!(f, "line {} has zero length", id),
+            EditorError::InvalidSplit(id) => write!(f, "line {} can only be split strictly between its ends", id),
+            EditorError::InvalidCorner(id) => write!(f, "corner at point {} cannot be rounded", id),
+            EditorError::InvalidLayer(id) => write!(f, "layer {} does not exist or cannot be removed", id),
+            EditorError::InvalidMerge(reason) => write!(f, "cannot merge: {}", reason),
+            EditorError::SharedConflict(id) => write!(f, "shared object {} would end up differently in the polygons using it", id),
+            EditorError::InvalidShape(reason) => write!(f, "invalid shape: {}", reason),
+            EditorError::InvalidOffset(reason) => write!(f, "cannot offset: {}", reason),
+            EditorError::InvalidScene(reason) => write!(f, "invalid scene: {}", reason)
+        }
+    }
+}
+
+impl std::error::Error for EditorError {}
+
+impl From<EditorError> for JsValue {
+    fn from(error: EditorError) -> JsValue {
+        js_sys::Error::new(error.to_string().as_str()).into()
+    }
+}
@@ -0,0 +1,376 @@
+use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::ids::IdAllocator;
+use crate::polygon::Polygon;
+use crate::utils::{calcualate_new_lines, flatten_polygon, get_bezier_point, get_distance_to_segment, get_line_length, is_simple_ring};
+
+const MAX_REPARAMETERIZATIONS: usize = 4;
+const EPSILON: f64 = 0.000001;
+// turns sharper than this stay corners of a fitted sketch
+const CORNER_ANGLE: f64 = 45.0;
+
+type CubicBezier = (PointCords, PointCords, PointCords, PointCords);
+
+/// Turns a closed freehand stroke into a polygon with the samples kept by Ramer–Douglas–Peucker as vertices.
+/// With curve fitting the stroke is cut only at sharp corners and every part between them is fitted
+/// with cubic Bézier segments (Schneider's algorithm).
+pub fn fit_sketch(samples: &[PointCords], tolerance: f64, fit_curves: bool, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Result<Polygon> {
+    let first = *samples.first().ok_or(EditorError::InvalidShape("sketch is too short"))?;
+    // the end of a stroke drawn back to its start would make an edge of almost zero length
+    let mut ring: Vec<PointCords> = samples.to_vec();
+    while ring.len() > 1 && get_line_length(ring[ring.len() - 1], first) < tolerance {
+        ring.pop();
+    }
+    ring.push(first);
+    let mut kept = simplify_ring(&ring, tolerance);
+    kept.pop();
+    if kept.len() < 3 {
+        return Err(EditorError::InvalidShape("sketch is too short"));
+    }
+
+    let (cords, beziers): (Vec<PointCords>, Vec<Option<(PointCords, PointCords)>>) = if fit_curves {
+        fit_ring(&ring, &kept, tolerance)
+            .iter()
+            .map(|(p1, b1, b2, _)| (*p1, Some((*b1, *b2))))
+            .unzip()
+    } else {
+        kept.iter().map(|i| (ring[*i], None)).unzip()
+    };
+    let points: Vec<Point> = cords
+        .iter()
+        .map(|p| Point { x: p.0, y: p.1, id: ids.allocate() })
+        .collect();
+    let mut lines = calcualate_new_lines(points.iter().collect(), ids);
+    // the line ending at vertex i follows the segment starting at vertex i - 1
+    for i in 0..lines.len() {
+        lines[i].bezier = beziers[(i + beziers.len() - 1) % beziers.len()];
+    }
+
+    let mut polygon = Polygon::new(points, lines, centroid_mode);
+    if polygon.points.len() < 3 || !is_simple_ring(&polygon.get_outline()) || !is_simple_ring(&flatten_polygon(&polygon)) {
+        return Err(EditorError::InvalidShape("sketch crosses itself"));
+    }
+    polygon.recalculate();
+    Ok(polygon)
+}
+
+/// Fits the closed ring between its corners, the simplified vertices give stable tangents at the cuts.
+fn fit_ring(ring: &[PointCords], kept: &[usize], tolerance: f64) -> Vec<CubicBezier> {
+    let count = kept.len();
+    let vertex = |j: usize| ring[kept[j % count]];
+    let is_corner = |j: usize| {
+        let incoming = sub(vertex(j + count), vertex(j + count - 1));
+        let outgoing = sub(vertex(j + 1), vertex(j));
+        get_angle(incoming, outgoing) > CORNER_ANGLE.to_radians()
+    };
+    let corners: Vec<usize> = (0..count).filter(|j| is_corner(*j)).collect();
+    // a stroke without corners is a single smooth loop starting at the first vertex
+    let cuts = if corners.is_empty() {vec![0]} else {corners.clone()};
+
+    (0..cuts.len())
+        .flat_map(|k| {
+            let (start, end) = (cuts[k], cuts[(k + 1) % cuts.len()]);
+            let span: Vec<PointCords> = if kept[end] > kept[start] {
+                ring[kept[start]..=kept[end]].to_vec()
+            } else {
+                ring[kept[start]..].iter().chain(ring[1..=kept[end]].iter()).copied().collect()
+            };
+            let start_tangent = if corners.is_empty() {
+                normalize(sub(vertex(start + 1), vertex(start + count - 1)))
+            } else {
+                normalize(sub(vertex(start + 1), vertex(start)))
+            };
+            let end_tangent = if corners.is_empty() {
+                mul(start_tangent, -1.0)
+            } else {
+                normalize(sub(vertex(end + count - 1), vertex(end)))
+            };
+            fit_span(&span, start_tangent, end_tangent, tolerance)
+        })
+        .collect()
+}
+
+/// Indices of the points kept by Ramer–Douglas–Peucker, the first and the last point are always kept.
+pub fn douglas_peucker(points: &[PointCords], tolerance: f64) -> Vec<usize> {
+    if points.len() < 3 {
+        return (0..points.len()).collect();
+    }
+    let last = points.len() - 1;
+    let (index, distance) = (1..last)
+        .map(|i| (i, get_distance_to_segment(points[i], points[0], points[last])))
+        .fold((0, -1.0), |farthest, current| if current.1 > farthest.1 {current} else {farthest});
+    if distance <= tolerance {
+        return vec![0, last];
+    }
+    let mut kept = douglas_peucker(&points[..=index], tolerance);
+    kept.pop();
+    kept.extend(douglas_peucker(&points[index..], tolerance).iter().map(|i| i + index));
+    kept
+}
+
+/// Simplifies a ring given with its first point repeated at the end. The ring is first split at the point
+/// farthest from the start, as a single chord from a point to itself cannot measure any distance.
+fn simplify_ring(ring: &[PointCords], tolerance: f64) -> Vec<usize> {
+    let farthest = (0..ring.len())
+        .fold(0, |farthest, i| if get_line_length(ring[0], ring[i]) > get_line_length(ring[0], ring[farthest]) {i} else {farthest});
+    if farthest == 0 {
+        return vec![0];
+    }
+    let mut kept = douglas_peucker(&ring[..=farthest], tolerance);
+    kept.pop();
+    kept.extend(douglas_peucker(&ring[farthest..], tolerance).iter().map(|i| i + farthest));
+    kept
+}
+
+/// Schneider's algorithm: fits cubic Béziers through the points, splitting at the worst point until
+/// every point lies within the tolerance.
+pub fn fit_cubic_beziers(points: &[PointCords], tolerance: f64) -> Vec<CubicBezier> {
+    if points.len() < 2 {
+        return vec![];
+    }
+    let last = points.len() - 1;
+    fit_span(points, normalize(sub(points[1], points[0])), normalize(sub(points[last - 1], points[last])), tolerance)
+}
+
+fn fit_span(points: &[PointCords], start_tangent: PointCords, end_tangent: PointCords, tolerance: f64) -> Vec<CubicBezier> {
+    let points: Vec<PointCords> = points
+        .iter()
+        .fold(vec![], |mut unique: Vec<PointCords>, p| {
+            if unique.last().is_none_or(|last| get_line_length(*last, *p) > EPSILON) {
+                unique.push(*p);
+            }
+            unique
+        });
+    let mut beziers: Vec<CubicBezier> = vec![];
+    if points.len() >= 2 {
+        fit_cubic(&points, start_tangent, end_tangent, tolerance*tolerance, &mut beziers);
+    }
+    beziers
+}
+
+fn fit_cubic(points: &[PointCords], start_tangent: PointCords, end_tangent: PointCords, error: f64, beziers: &mut Vec<CubicBezier>) {
+    let last = points.len() - 1;
+    if points.len() == 2 {
+        let distance = get_line_length(points[0], points[1])/3.0;
+        beziers.push((points[0], add(points[0], mul(start_tangent, distance)), add(points[1], mul(end_tangent, distance)), points[1]));
+        return;
+    }
+
+    let mut parameters = get_chord_length_parameters(points);
+    let mut bezier = generate_bezier(points, &parameters, start_tangent, end_tangent);
+    let (mut max_error, mut split) = get_max_error(points, bezier, &parameters);
+    if max_error < error {
+        beziers.push(bezier);
+        return;
+    }
+    if max_error < 4.0*error {
+        for _ in 0..MAX_REPARAMETERIZATIONS {
+            parameters = reparameterize(points, &parameters, bezier);
+            bezier = generate_bezier(points, &parameters, start_tangent, end_tangent);
+            let (new_error, new_split) = get_max_error(points, bezier, &parameters);
+            max_error = new_error;
+            split = new_split;
+            if max_error < error {
+                beziers.push(bezier);
+                return;
+            }
+        }
+    }
+
+    let split = split.max(1).min(last - 1);
+    let center_tangent = match normalize(sub(points[split - 1], points[split + 1])) {
+        PointCords(x, y) if x == 0.0 && y == 0.0 => normalize(sub(points[split - 1], points[split])),
+        tangent => tangent
+    };
+    fit_cubic(&points[..=split], start_tangent, center_tangent, error, beziers);
+    fit_cubic(&points[split..], mul(center_tangent, -1.0), end_tangent, error, beziers);
+}
+
+/// Least squares placement of the inner control points along the end tangents.
+fn generate_bezier(points: &[PointCords], parameters: &[f64], start_tangent: PointCords, end_tangent: PointCords) -> CubicBezier {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let mut c = [[0.0, 0.0], [0.0, 0.0]];
+    let mut x = [0.0, 0.0];
+    for (p, u) in points.iter().zip(parameters.iter()) {
+        let (b0, b1, b2, b3) = get_bernstein(*u);
+        let a1 = mul(start_tangent, b1);
+        let a2 = mul(end_tangent, b2);
+        c[0][0] += dot(a1, a1);
+        c[0][1] += dot(a1, a2);
+        c[1][1] += dot(a2, a2);
+        let rest = sub(*p, add(mul(first, b0 + b1), mul(last, b2 + b3)));
+        x[0] += dot(a1, rest);
+        x[1] += dot(a2, rest);
+    }
+    c[1][0] = c[0][1];
+
+    let det_c0_c1 = c[0][0]*c[1][1] - c[1][0]*c[0][1];
+    let det_c0_x = c[0][0]*x[1] - c[1][0]*x[0];
+    let det_x_c1 = x[0]*c[1][1] - x[1]*c[0][1];
+    let (alpha1, alpha2) = if det_c0_c1.abs() < EPSILON {(0.0, 0.0)} else {(det_x_c1/det_c0_c1, det_c0_x/det_c0_c1)};
+
+    let segment_length = get_line_length(first, last);
+    let (alpha1, alpha2) = if alpha1 < EPSILON*segment_length || alpha2 < EPSILON*segment_length {
+        (segment_length/3.0, segment_length/3.0)
+    } else {
+        (alpha1, alpha2)
+    };
+    (first, add(first, mul(start_tangent, alpha1)), add(last, mul(end_tangent, alpha2)), last)
+}
+
+fn get_max_error(points: &[PointCords], (p1, b1, b2, p2): CubicBezier, parameters: &[f64]) -> (f64, usize) {
+    (1..points.len() - 1)
+        .map(|i| {
+            let distance = get_line_length(get_bezier_point(p1, b1, b2, p2, parameters[i]), points[i]);
+            (distance*distance, i)
+        })
+        .fold((0.0, points.len()/2), |max, current| if current.0 > max.0 {current} else {max})
+}
+
+/// One Newton–Raphson step moving every parameter towards the closest point of the curve.
+fn reparameterize(points: &[PointCords], parameters: &[f64], (p1, b1, b2, p2): CubicBezier) -> Vec<f64> {
+    let first_derivative = [mul(sub(b1, p1), 3.0), mul(sub(b2, b1), 3.0), mul(sub(p2, b2), 3.0)];
+    let second_derivative = [mul(sub(first_derivative[1], first_derivative[0]), 2.0), mul(sub(first_derivative[2], first_derivative[1]), 2.0)];
+    points
+        .iter()
+        .zip(parameters.iter())
+        .map(|(p, u)| {
+            let q = get_bezier_point(p1, b1, b2, p2, *u);
+            let q1 = add(add(mul(first_derivative[0], (1.0 - u)*(1.0 - u)), mul(first_derivative[1], 2.0*u*(1.0 - u))), mul(first_derivative[2], u*u));
+            let q2 = add(mul(second_derivative[0], 1.0 - u), mul(second_derivative[1], *u));
+            let difference = sub(q, *p);
+            let denominator = dot(q1, q1) + dot(difference, q2);
+            if denominator.abs() < EPSILON {*u} else {(u - dot(difference, q1)/denominator).clamp(0.0, 1.0)}
+        })
+        .collect()
+}
+
+fn get_chord_length_parameters(points: &[PointCords]) -> Vec<f64> {
+    let mut lengths = vec![0.0];
+    for i in 1..points.len() {
+        lengths.push(lengths[i - 1] + get_line_length(points[i - 1], points[i]));
+    }
+    let total = lengths[lengths.len() - 1];
+    lengths
+        .iter()
+        .map(|length| if total > 0.0 {length/total} else {0.0})
+        .collect()
+}
+
+fn get_bernstein(u: f64) -> (f64, f64, f64, f64) {
+    let v = 1.0 - u;
+    (v*v*v, 3.0*u*v*v, 3.0*u*u*v, u*u*u)
+}
+
+fn add(a: PointCords, b: PointCords) -> PointCords {
+    PointCords(a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: PointCords, b: PointCords) -> PointCords {
+    PointCords(a.0 - b.0, a.1 - b.1)
+}
+
+fn mul(a: PointCords, factor: f64) -> PointCords {
+    PointCords(a.0*factor, a.1*factor)
+}
+
+fn dot(a: PointCords, b: PointCords) -> f64 {
+    a.0*b.0 + a.1*b.1
+}
+
+fn get_angle(a: PointCords, b: PointCords) -> f64 {
+    (a.0*b.1 - a.1*b.0).atan2(dot(a, b)).abs()
+}
+
+fn normalize(a: PointCords) -> PointCords {
+    let length = get_line_length(a, PointCords(0.0, 0.0));
+    if length < EPSILON {PointCords(0.0, 0.0)} else {mul(a, 1.0/length)}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_circle(samples: usize, radius: f64) -> Vec<PointCords> {
+        (0..samples)
+            .map(|i| {
+                let angle = (i as f64)*2.0*std::f64::consts::PI/(samples as f64);
+                PointCords(100.0 + radius*angle.cos(), 100.0 + radius*angle.sin())
+            })
+            .collect()
+    }
+
+    /// Largest distance from a point to the closest point of the curves, sampled densely.
+    fn get_fit_error(points: &[PointCords], beziers: &[CubicBezier]) -> f64 {
+        let curve: Vec<PointCords> = beziers
+            .iter()
+            .flat_map(|(p1, b1, b2, p2)| (0..=200).map(move |i| get_bezier_point(*p1, *b1, *b2, *p2, (i as f64)/200.0)))
+            .collect();
+        points
+            .iter()
+            .map(|p| curve.iter().map(|q| get_line_length(*p, *q)).fold(f64::INFINITY, f64::min))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn douglas_peucker_keeps_only_far_points() {
+        let points = vec![
+            PointCords(0.0, 0.0), PointCords(1.0, 0.1), PointCords(2.0, -0.1), PointCords(3.0, 5.0),
+            PointCords(4.0, 6.0), PointCords(5.0, 7.0), PointCords(6.0, 8.1), PointCords(7.0, 9.0)
+        ];
+        assert_eq!(douglas_peucker(&points, 0.5), vec![0, 2, 3, 7]);
+        assert_eq!(douglas_peucker(&points, 100.0), vec![0, 7]);
+        assert_eq!(douglas_peucker(&points[..2], 0.5), vec![0, 1]);
+    }
+
+    #[test]
+    fn ring_simplifies_to_its_corners() {
+        let mut ring = vec![];
+        for (a, b) in [((0.0, 0.0), (10.0, 0.0)), ((10.0, 0.0), (10.0, 10.0)), ((10.0, 10.0), (0.0, 10.0)), ((0.0, 10.0), (0.0, 0.0))] {
+            for i in 0..5 {
+                let t = (i as f64)/5.0;
+                ring.push(PointCords(a.0 + t*(b.0 - a.0), a.1 + t*(b.1 - a.1)));
+            }
+        }
+        ring.push(ring[0]);
+        let kept: Vec<PointCords> = simplify_ring(&ring, 0.1).iter().map(|i| ring[*i]).collect();
+        assert_eq!(kept.len(), 5);
+        for corner in [PointCords(0.0, 0.0), PointCords(10.0, 0.0), PointCords(10.0, 10.0), PointCords(0.0, 10.0)] {
+            assert!(kept.iter().any(|p| get_line_length(*p, corner) < EPSILON));
+        }
+    }
+
+    #[test]
+    fn schneider_fit_stays_within_tolerance() {
+        let arc: Vec<PointCords> = get_circle(80, 50.0)[..30].to_vec();
+        let beziers = fit_cubic_beziers(&arc, 0.5);
+        assert!(!beziers.is_empty());
+        assert!(get_line_length(beziers[0].0, arc[0]) < EPSILON);
+        assert!(get_line_length(beziers[beziers.len() - 1].3, arc[arc.len() - 1]) < EPSILON);
+        assert!(get_fit_error(&arc, &beziers) <= 0.5);
+    }
+
+    #[test]
+    fn circle_sketch_fits_smooth_curves() {
+        let mut ids = IdAllocator::new();
+        let samples = get_circle(120, 50.0);
+        let polygon = fit_sketch(&samples, 1.0, true, &mut ids, CentroidMode::Area).unwrap();
+        assert!(polygon.lines.iter().all(|line| line.bezier.is_some()));
+        // a circle needs a few cubic segments, not one per sample
+        assert!(polygon.points.len() <= 8, "{} segments", polygon.points.len());
+        let beziers: Vec<CubicBezier> = polygon.lines
+            .iter()
+            .map(|line| {
+                let (p1, p2) = polygon.get_line_cords(line).unwrap();
+                let (b1, b2) = line.bezier.unwrap();
+                (p1, b1, b2, p2)
+            })
+            .collect();
+        assert!(get_fit_error(&samples, &beziers) <= 1.0);
+
+        let straight = fit_sketch(&samples, 1.0, false, &mut ids, CentroidMode::Area).unwrap();
+        assert!(straight.lines.iter().all(|line| line.bezier.is_none()));
+        assert!(fit_sketch(&samples[..2], 1.0, false, &mut ids, CentroidMode::Area).is_err());
+    }
+}
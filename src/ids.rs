@@ -0,0 +1,66 @@
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+
+/// Hands out ids shared by points and lines of the whole scene. Ids are never reused,
+/// so snapshots restored by undo or loaded from a file cannot collide with new objects.
+#[derive(Clone, Copy)]
+pub struct IdAllocator {
+    next: u32
+}
+
+impl Default for IdAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdAllocator {
+    pub fn new() -> IdAllocator {
+        IdAllocator { next: 1 }
+    }
+
+    pub fn allocate(&mut self) -> u32 {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+
+    pub fn peek(&self) -> u32 {
+        self.next
+    }
+
+    /// The largest id cannot be reserved, nothing would be left to hand out after it.
+    pub fn reserve(&mut self, id: u32) -> Result<()> {
+        let next = id.checked_add(1).ok_or(EditorError::InvalidScene("id is too large"))?;
+        if next > self.next {
+            self.next = next;
+        }
+        Ok(())
+    }
+
+    pub fn reserve_polygons(&mut self, polygons: &[Polygon]) -> Result<()> {
+        polygons
+            .iter()
+            .try_for_each(|polygon| {
+                polygon.points.iter().try_for_each(|point| self.reserve(point.id))?;
+                polygon.lines.iter().try_for_each(|line| self.reserve(line.id))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_skips_used_ids_and_rejects_the_largest() {
+        let mut ids = IdAllocator::new();
+        ids.reserve(41).unwrap();
+        ids.reserve(7).unwrap();
+        assert_eq!(ids.allocate(), 42);
+        assert!(ids.reserve(u32::MAX).is_err());
+        assert_eq!(ids.peek(), 43);
+        ids.reserve(u32::MAX - 1).unwrap();
+        assert_eq!(ids.peek(), u32::MAX);
+    }
+}
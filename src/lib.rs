@@ -8,3 +8,17 @@ pub mod polygon;
 pub mod utils;
 pub mod draw;
 pub mod bresenham;
+pub mod triangulation;
+pub mod convex;
+pub mod offset;
+pub mod transform;
+pub mod error;
+pub mod ids;
+pub mod scene;
+pub mod merge;
+pub mod shapes;
+pub mod fitting;
+pub mod simplify;
+pub mod corner;
+pub mod dimensions;
+pub mod units;
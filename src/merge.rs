@@ -0,0 +1,206 @@
+use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::utils::{get_line_length, is_simple_ring};
+
+const MERGE_DISTANCE: f64 = 0.001;
+
+type RelationUpdates = Vec<(u32, Option<u32>)>;
+
+impl Polygon {
+    /// Collapses the edge between two adjacent vertices into the target vertex.
+    /// Returns the relations of other lines that have to be updated: (line, new related line).
+    pub fn merge_points(&mut self, source_id: u32, target_id: u32) -> Result<RelationUpdates> {
+        self.get_point_by_id(source_id)?;
+        self.get_point_by_id(target_id)?;
+        if self.points.len() <= self.get_min_points_count() {
+            return Err(EditorError::InvalidMerge("shape would have too few vertices"));
+        }
+        let shared_id = self.find_line_between(source_id, target_id)
+            .ok_or(EditorError::InvalidMerge("vertices are not adjacent"))?;
+        let shared_index = self.lines
+            .iter()
+            .position(|line| line.id == shared_id)
+            .ok_or(EditorError::LineNotFound(shared_id))?;
+
+        let removed = self.lines.remove(shared_index);
+        self.lines
+            .iter_mut()
+            .for_each(|line| {
+                if line.points.0 == source_id {
+                    line.points.0 = target_id;
+                }
+                if line.points.1 == source_id {
+                    line.points.1 = target_id;
+                }
+            });
+        self.points.retain(|point| point.id != source_id);
+        self.recalculate();
+        Ok(removed.relation.iter().map(|line_id| (*line_id, None)).collect())
+    }
+
+    /// Replaces a vertex with another one, lines keep pointing at the same corner.
+    pub fn replace_point(&mut self, old_id: u32, point: Point) {
+        self.points
+            .iter_mut()
+            .filter(|other| other.id == old_id)
+            .for_each(|other| *other = point);
+        self.lines
+            .iter_mut()
+            .for_each(|line| {
+                if line.points.0 == old_id {
+                    line.points.0 = point.id;
+                }
+                if line.points.1 == old_id {
+                    line.points.1 = point.id;
+                }
+            });
+    }
+
+    /// Replaces a line with a chain of lines running between the same vertices through the given new vertices.
+    /// The chain may run in either direction.
+    pub fn replace_line(&mut self, line_id: u32, points: &[Point], lines: &[Line]) -> Result<()> {
+        let line_index = self.lines
+            .iter()
+            .position(|line| line.id == line_id)
+            .ok_or(EditorError::LineNotFound(line_id))?;
+        let line = self.lines[line_index];
+        let (points, lines): (Vec<Point>, Vec<Line>) = match (lines.first(), lines.last()) {
+            (Some(first), Some(last)) if (first.points.0, last.points.1) == line.points => (points.to_vec(), lines.to_vec()),
+            (Some(first), Some(last)) if (last.points.1, first.points.0) == line.points => (
+                points.iter().rev().copied().collect(),
+                lines
+                    .iter()
+                    .rev()
+                    .map(|line| Line {
+                        points: (line.points.1, line.points.0),
+                        bezier: line.bezier.map(|(b1, b2)| (b2, b1)),
+                        ..*line
+                    })
+                    .collect()
+            ),
+            _ => return Err(EditorError::InvalidSplit(line_id))
+        };
+        let point_index = self.points
+            .iter()
+            .position(|point| point.id == line.points.0)
+            .ok_or(EditorError::PointNotFound(line.points.0))?;
+
+        self.lines.splice(line_index..line_index + 1, lines);
+        self.points.splice(point_index + 1..point_index + 1, points);
+        self.update_center();
+        Ok(())
+    }
+
+    pub fn find_line_between(&self, p1_id: u32, p2_id: u32) -> Option<u32> {
+        self.lines
+            .iter()
+            .find(|line| line.points == (p1_id, p2_id) || line.points == (p2_id, p1_id))
+            .map(|line| line.id)
+    }
+
+    /// Finds a line of the other polygon lying on the given line of this polygon.
+    pub fn find_shared_line(&self, other: &Polygon, line_id: u32) -> Result<Option<u32>> {
+        let (a1, a2) = self.get_line_cords(self.get_line_reference_inmut(line_id)?)?;
+        Ok(other.lines
+            .iter()
+            .find(|line| match other.get_line_cords(line) {
+                Ok((b1, b2)) => (is_same_point(a1, b2) && is_same_point(a2, b1)) || (is_same_point(a1, b1) && is_same_point(a2, b2)),
+                Err(_) => false
+            })
+            .map(|line| line.id))
+    }
+
+    /// Joins two polygons along a shared edge, both copies of the edge are removed and all other lines keep their attributes.
+    /// Returns the merged polygon and the relations of other lines that have to be updated.
+    pub fn merge_along(&self, other: &Polygon, line_id: u32) -> Result<(Polygon, RelationUpdates)> {
+        if !self.is_closed() || !other.is_closed() {
+            return Err(EditorError::InvalidMerge("open paths cannot be joined along an edge"));
+        }
+        let other_line_id = self.find_shared_line(other, line_id)?
+            .ok_or(EditorError::InvalidMerge("polygons do not share this edge"))?;
+        let line = *self.get_line_reference_inmut(line_id)?;
+        let (a1, _) = self.get_line_cords(&line)?;
+        let other_line = *other.get_line_reference_inmut(other_line_id)?;
+        let other = if is_same_point(a1, other.get_point_by_id(other_line.points.0)?) {other.reversed()} else {other.clone()};
+        let (a1_id, a2_id) = line.points;
+        let (b1_id, b2_id) = other.get_line_by_id(other_line_id)?;
+
+        let remap = |id: u32| if id == b1_id {a2_id} else if id == b2_id {a1_id} else {id};
+        let mut points = rotate_to(&self.points, a2_id)?;
+        let other_points = rotate_to(&other.points, b2_id)?;
+        points.extend(other_points[1..other_points.len() - 1].iter().copied());
+
+        let candidates: Vec<Line> = self.lines
+            .iter()
+            .filter(|line| line.id != line_id)
+            .copied()
+            .chain(other.lines
+                .iter()
+                .filter(|line| line.id != other_line_id)
+                .map(|line| Line { points: (remap(line.points.0), remap(line.points.1)), ..*line }))
+            .collect();
+        let lines = (0..points.len())
+            .map(|i| {
+                let ends = (points[i].id, points[(i + 1) % points.len()].id);
+                candidates
+                    .iter()
+                    .find(|line| line.points == ends)
+                    .copied()
+                    .ok_or(EditorError::InvalidMerge("polygons share more than one edge"))
+            })
+            .collect::<Result<Vec<Line>>>()?;
+
+        let mut merged = Polygon::new(points, lines, self.centroid_mode);
+        if merged.lines.len() != candidates.len() || !is_simple_ring(&merged.get_outline()) {
+            return Err(EditorError::InvalidMerge("merged outline is not simple"));
+        }
+        let dimensions = self.dimensions
+            .iter()
+            .copied()
+            .chain(other.dimensions.iter().map(|dimension| match dimension {
+                Dimension::Angle(point_id) => Dimension::Angle(remap(*point_id)),
+                Dimension::Length(line_id) => Dimension::Length(*line_id)
+            }));
+        for dimension in dimensions {
+            if merged.is_valid_dimension(&dimension) && !merged.dimensions.contains(&dimension) {
+                merged.dimensions.push(dimension);
+            }
+        }
+        merged.layer = self.layer;
+        merged.recalculate();
+
+        let relation_updates = [line.relation, other_line.relation]
+            .iter()
+            .filter_map(|relation| *relation)
+            .filter(|related| *related != line_id && *related != other_line_id)
+            .map(|related| (related, None))
+            .collect();
+        Ok((merged, relation_updates))
+    }
+
+    pub fn reversed(&self) -> Polygon {
+        let mut polygon = self.clone();
+        polygon.points.reverse();
+        polygon.lines.reverse();
+        polygon.lines
+            .iter_mut()
+            .for_each(|line| {
+                line.points = (line.points.1, line.points.0);
+                line.bezier = line.bezier.map(|(b1, b2)| (b2, b1));
+            });
+        polygon
+    }
+}
+
+pub fn is_same_point(p1: PointCords, p2: PointCords) -> bool {
+    get_line_length(p1, p2) < MERGE_DISTANCE
+}
+
+fn rotate_to(points: &[Point], first_id: u32) -> Result<Vec<Point>> {
+    let start = points
+        .iter()
+        .position(|point| point.id == first_id)
+        .ok_or(EditorError::PointNotFound(first_id))?;
+    Ok(points[start..].iter().chain(points[..start].iter()).copied().collect())
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use crate::data_models::{PointCords, JoinStyle};
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::utils::{cross, get_signed_area, flatten_polygon, get_line_length, get_segments_intersection, get_distance_to_segment};
+
+const EPSILON: f64 = 0.0000001;
+const ROUND_SEGMENTS: f64 = 16.0;
+const DISTANCE_TOLERANCE: f64 = 0.001;
+
+impl Polygon {
+    pub fn offset(&self, distance: f64, join_style: JoinStyle, miter_limit: f64) -> Result<Vec<Vec<PointCords>>> {
+        if !distance.is_finite() || distance == 0.0 {
+            return Err(EditorError::InvalidOffset("distance has to be a non-zero number"));
+        }
+        if !miter_limit.is_finite() || miter_limit <= 0.0 {
+            return Err(EditorError::InvalidOffset("miter limit has to be a positive number"));
+        }
+        Ok(get_offset_outlines(&flatten_polygon(self), distance, join_style, miter_limit))
+    }
+}
+
+fn get_normal(p1: PointCords, p2: PointCords) -> PointCords {
+    let length = get_line_length(p1, p2);
+    PointCords((p2.1 - p1.1)/length, -(p2.0 - p1.0)/length)
+}
+
+fn get_lines_intersection(p1: PointCords, d1: PointCords, p2: PointCords, d2: PointCords) -> Option<PointCords> {
+    let denominator = d1.0*d2.1 - d1.1*d2.0;
+    if denominator.abs() < EPSILON {
+        return None;
+    }
+    let t = ((p2.0 - p1.0)*d2.1 - (p2.1 - p1.1)*d2.0)/denominator;
+    Some(PointCords(p1.0 + t*d1.0, p1.1 + t*d1.1))
+}
+
+fn get_distance_to_outline(p: PointCords, outline: &[PointCords]) -> f64 {
+    (0..outline.len())
+        .map(|i| get_distance_to_segment(p, outline[i], outline[(i + 1) % outline.len()]))
+        .fold(f64::MAX, f64::min)
+}
+
+fn add_join(ring: &mut Vec<PointCords>, prev: PointCords, p: PointCords, next: PointCords, distance: f64, join_style: JoinStyle, miter_limit: f64) {
+    let n0 = get_normal(prev, p);
+    let n1 = get_normal(p, next);
+    let q0 = PointCords(p.0 + distance*n0.0, p.1 + distance*n0.1);
+    let q1 = PointCords(p.0 + distance*n1.0, p.1 + distance*n1.1);
+
+    if distance*cross(prev, p, next) <= 0.0 {
+        ring.push(q0);
+        ring.push(p);
+        ring.push(q1);
+        return;
+    }
+
+    match join_style {
+        JoinStyle::Miter => {
+            let miter = get_lines_intersection(q0, PointCords(p.0 - prev.0, p.1 - prev.1), q1, PointCords(next.0 - p.0, next.1 - p.1));
+            match miter {
+                Some(m) if get_line_length(m, p) <= miter_limit*distance.abs() => ring.push(m),
+                _ => {
+                    ring.push(q0);
+                    ring.push(q1);
+                }
+            }
+        },
+        JoinStyle::Bevel => {
+            ring.push(q0);
+            ring.push(q1);
+        },
+        JoinStyle::Round => {
+            let start = n0.1.atan2(n0.0);
+            let mut sweep = n1.1.atan2(n1.0) - start;
+            while sweep > std::f64::consts::PI {sweep -= 2.0*std::f64::consts::PI;}
+            while sweep < -std::f64::consts::PI {sweep += 2.0*std::f64::consts::PI;}
+            let steps = ((sweep.abs()/std::f64::consts::PI)*ROUND_SEGMENTS).ceil().max(1.0) as u32;
+            for k in 0..=steps {
+                let angle = start + sweep*(k as f64)/(steps as f64);
+                ring.push(PointCords(p.0 + distance*angle.cos(), p.1 + distance*angle.sin()));
+            }
+        }
+    }
+}
+
+fn split_into_loops(ring: &[PointCords]) -> Vec<Vec<PointCords>> {
+    let mut crossings: Vec<Vec<(f64, usize, PointCords)>> = vec![vec![]; ring.len()];
+    let mut crossing_id = 0;
+    for i in 0..ring.len() {
+        for j in (i + 2)..ring.len() {
+            if i == 0 && j == ring.len() - 1 {
+                continue;
+            }
+            if let Some((t, u, p)) = get_segments_intersection(ring[i], ring[(i + 1) % ring.len()], ring[j], ring[(j + 1) % ring.len()]) {
+                crossings[i].push((t, crossing_id, p));
+                crossings[j].push((u, crossing_id, p));
+                crossing_id += 1;
+            }
+        }
+    }
+
+    for k in 0..ring.len() {
+        for j in 0..ring.len() {
+            if j == k || (j + 1) % ring.len() == k {
+                continue;
+            }
+            let (a, b) = (ring[j], ring[(j + 1) % ring.len()]);
+            let length = (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2);
+            if length < EPSILON {
+                continue;
+            }
+            let u = ((ring[k].0 - a.0)*(b.0 - a.0) + (ring[k].1 - a.1)*(b.1 - a.1))/length;
+            if u > EPSILON && u < 1.0 - EPSILON && get_distance_to_segment(ring[k], a, b) < EPSILON {
+                crossings[k].push((0.0, crossing_id, ring[k]));
+                crossings[j].push((u, crossing_id, ring[k]));
+                crossing_id += 1;
+            }
+        }
+    }
+
+    let mut loops: Vec<Vec<PointCords>> = vec![];
+    let mut path: Vec<(PointCords, Option<usize>)> = vec![];
+    let mut positions: HashMap<usize, usize> = HashMap::new();
+    for i in 0..ring.len() {
+        path.push((ring[i], None));
+        crossings[i].sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, id, p) in crossings[i].iter() {
+            match positions.get(id) {
+                Some(k) => {
+                    let k = *k;
+                    loops.push(path[k..].iter().map(|(p, _)| *p).collect());
+                    path[k + 1..]
+                        .iter()
+                        .for_each(|(_, id)| {id.map(|id| positions.remove(&id));});
+                    path.truncate(k + 1);
+                },
+                None => {
+                    positions.insert(*id, path.len());
+                    path.push((*p, Some(*id)));
+                }
+            }
+        }
+    }
+    loops.push(path.iter().map(|(p, _)| *p).collect());
+    loops
+}
+
+fn remove_duplicates(outline: Vec<PointCords>) -> Vec<PointCords> {
+    let mut result: Vec<PointCords> = vec![];
+    outline
+        .into_iter()
+        .for_each(|p| {
+            match result.last() {
+                Some(last) if get_line_length(*last, p) < EPSILON => {},
+                _ => result.push(p)
+            }
+        });
+    while result.len() > 1 && get_line_length(result[0], result[result.len() - 1]) < EPSILON {
+        result.pop();
+    }
+    result
+}
+
+fn remove_collinear(outline: Vec<PointCords>) -> Vec<PointCords> {
+    let mut result = outline;
+    let mut i = 0;
+    while i < result.len() && result.len() > 3 {
+        let prev = result[(i + result.len() - 1) % result.len()];
+        let next = result[(i + 1) % result.len()];
+        if cross(prev, result[i], next).abs() < EPSILON {
+            result.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+pub fn get_offset_outlines(outline: &[PointCords], distance: f64, join_style: JoinStyle, miter_limit: f64) -> Vec<Vec<PointCords>> {
+    let mut outline = remove_duplicates(outline.to_vec());
+    if outline.len() < 3 || distance.abs() < EPSILON {
+        return vec![];
+    }
+    if get_signed_area(&outline) < 0.0 {
+        outline.reverse();
+    }
+
+    let mut ring: Vec<PointCords> = vec![];
+    for i in 0..outline.len() {
+        let prev = outline[(i + outline.len() - 1) % outline.len()];
+        let next = outline[(i + 1) % outline.len()];
+        add_join(&mut ring, prev, outline[i], next, distance, join_style, miter_limit);
+    }
+
+    split_into_loops(&remove_duplicates(ring))
+        .into_iter()
+        .map(|offset_loop| remove_collinear(remove_duplicates(offset_loop)))
+        .filter(|offset_loop| offset_loop.len() >= 3 && get_signed_area(offset_loop) > EPSILON)
+        .filter(|offset_loop| offset_loop
+            .iter()
+            .all(|p| get_distance_to_outline(*p, &outline) >= distance.abs()*(1.0 - DISTANCE_TOLERANCE)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_models::{CentroidMode, Point};
+    use crate::ids::IdAllocator;
+    use crate::utils::calcualate_new_lines;
+
+    fn square(size: f64) -> Vec<PointCords> {
+        vec![PointCords(0.0, 0.0), PointCords(size, 0.0), PointCords(size, size), PointCords(0.0, size)]
+    }
+
+    #[test]
+    fn outset_square_with_each_join() {
+        let miter = get_offset_outlines(&square(10.0), 1.0, JoinStyle::Miter, 2.0);
+        assert_eq!(miter.len(), 1);
+        assert_eq!(miter[0].len(), 4);
+        assert!((get_signed_area(&miter[0]) - 144.0).abs() < EPSILON);
+
+        // the miter of a right angle is sqrt(2) times the distance, a lower limit cuts the corners off
+        let limited = get_offset_outlines(&square(10.0), 1.0, JoinStyle::Miter, 1.2);
+        let bevel = get_offset_outlines(&square(10.0), 1.0, JoinStyle::Bevel, 2.0);
+        for outlines in [limited, bevel] {
+            assert_eq!(outlines.len(), 1);
+            assert_eq!(outlines[0].len(), 8);
+            assert!((get_signed_area(&outlines[0]) - 142.0).abs() < EPSILON);
+        }
+
+        let round = get_offset_outlines(&square(10.0), 1.0, JoinStyle::Round, 2.0);
+        assert_eq!(round.len(), 1);
+        assert!(round[0].len() > 8);
+        let area = get_signed_area(&round[0]);
+        assert!(area > 143.0 && area < 140.0 + std::f64::consts::PI);
+    }
+
+    #[test]
+    fn inset_keeps_vertex_count() {
+        let mut outline = square(10.0);
+        outline.reverse();
+        let outlines = get_offset_outlines(&outline, -2.0, JoinStyle::Miter, 2.0);
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[0].len(), 4);
+        assert!((get_signed_area(&outlines[0]) - 36.0).abs() < EPSILON);
+        assert!(outlines[0].iter().all(|p| (get_distance_to_outline(*p, &outline) - 2.0).abs() < EPSILON));
+    }
+
+    #[test]
+    fn dumbbell_inset_splits_into_two_loops() {
+        let dumbbell = vec![
+            PointCords(0.0, 0.0), PointCords(10.0, 0.0), PointCords(10.0, 4.0), PointCords(20.0, 4.0),
+            PointCords(20.0, 0.0), PointCords(30.0, 0.0), PointCords(30.0, 10.0), PointCords(20.0, 10.0),
+            PointCords(20.0, 6.0), PointCords(10.0, 6.0), PointCords(10.0, 10.0), PointCords(0.0, 10.0)
+        ];
+        // the bar between the squares is only 2 wide and vanishes
+        let outlines = get_offset_outlines(&dumbbell, -1.5, JoinStyle::Miter, 2.0);
+        assert_eq!(outlines.len(), 2);
+        for outline in outlines.iter() {
+            assert_eq!(outline.len(), 4);
+            assert!((get_signed_area(outline) - 49.0).abs() < EPSILON);
+        }
+
+        let thin = get_offset_outlines(&dumbbell, -0.5, JoinStyle::Miter, 2.0);
+        assert_eq!(thin.len(), 1);
+        assert_eq!(thin[0].len(), 12);
+    }
+
+    #[test]
+    fn inset_beyond_inradius_is_empty() {
+        assert!(get_offset_outlines(&square(10.0), -5.0, JoinStyle::Miter, 2.0).is_empty());
+        assert!(get_offset_outlines(&square(10.0), -6.0, JoinStyle::Round, 2.0).is_empty());
+    }
+
+    #[test]
+    fn offset_rejects_invalid_parameters() {
+        let mut ids = IdAllocator::new();
+        let points: Vec<Point> = square(10.0)
+            .iter()
+            .map(|p| Point { x: p.0, y: p.1, id: ids.allocate() })
+            .collect();
+        let lines = calcualate_new_lines(points.iter().collect(), &mut ids);
+        let polygon = Polygon::new(points, lines, CentroidMode::VertexMean);
+        assert!(polygon.offset(f64::NAN, JoinStyle::Miter, 2.0).is_err());
+        assert!(polygon.offset(f64::INFINITY, JoinStyle::Miter, 2.0).is_err());
+        assert!(polygon.offset(0.0, JoinStyle::Miter, 2.0).is_err());
+        assert!(polygon.offset(1.0, JoinStyle::Miter, -1.0).is_err());
+        assert!(polygon.offset(1.0, JoinStyle::Miter, f64::NAN).is_err());
+        assert_eq!(polygon.offset(1.0, JoinStyle::Bevel, 2.0).unwrap().len(), 1);
+    }
+}
@@ -1,78 +1,231 @@
 use crate::canvas::PressedObject;
 use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::ids::IdAllocator;
 use crate::utils::*;
 
 impl Polygon {
-    pub fn get_point_by_id(&self, id: u32) -> PointCords{
+    pub fn new(points: Vec<Point>, lines: Vec<Line>, centroid_mode: CentroidMode) -> Polygon {
+        let mut polygon = Polygon {
+            points,
+            lines,
+            center: PointCords(0.0, 0.0),
+            centroid_mode,
+            dimensions: vec![],
+            layer: 0
+        };
+        polygon.update_center();
+        polygon
+    }
+
+    pub fn update_center(&mut self) {
+        if let Some(center) = get_centroid(self) {
+            self.center = center;
+        }
+    }
+
+    /// Open paths have one line less than vertices, the closing line of a polygon is missing.
+    pub fn is_closed(&self) -> bool {
+        !self.lines.is_empty() && self.lines.len() == self.points.len()
+    }
+
+    pub fn get_min_points_count(&self) -> usize {
+        if self.is_closed() {3} else {2}
+    }
+
+    pub fn get_point_by_id(&self, id: u32) -> Result<PointCords> {
         self.points
             .iter()
             .find(|point| point.id == id)
-            .and_then(|point| Some(PointCords(point.x, point.y)))
-            .unwrap()
+            .map(|point| PointCords(point.x, point.y))
+            .ok_or(EditorError::PointNotFound(id))
     }
 
-    pub fn get_point_reference(&mut self, id: u32) -> &mut Point {
+    pub fn get_point_reference(&mut self, id: u32) -> Result<&mut Point> {
         self.points
             .iter_mut()
             .find(|point| point.id == id)
-            .unwrap()
+            .ok_or(EditorError::PointNotFound(id))
     }
 
-    pub fn modify_point_coordinates(&mut self, id: u32, coordinates: (f64, f64)) {
-        let edited_point = self.points
-            .iter_mut()
-            .find(|point| point.id == id)
-            .unwrap();
+    pub fn modify_point_coordinates(&mut self, id: u32, coordinates: (f64, f64)) -> Result<()> {
+        let edited_point = self.get_point_reference(id)?;
 
         edited_point.x = edited_point.x + coordinates.0;
         edited_point.y = edited_point.y + coordinates.1;
+        Ok(())
     }
 
-    pub fn get_line_reference(&mut self, id: u32) -> &mut Line {
+    pub fn get_line_reference(&mut self, id: u32) -> Result<&mut Line> {
         self.lines
             .iter_mut()
             .find(|line| line.id == id)
-            .unwrap()
+            .ok_or(EditorError::LineNotFound(id))
     }
 
-    pub fn get_line_reference_inmut(&self, id: u32) -> &Line {
+    pub fn get_line_reference_inmut(&self, id: u32) -> Result<&Line> {
         self.lines
             .iter()
             .find(|line| line.id == id)
-            .unwrap()
+            .ok_or(EditorError::LineNotFound(id))
     }
 
-    pub fn get_line_by_id(&self, id: u32) -> (u32, u32) {
-        self.lines
-            .iter()
-            .find(|line| line.id == id)
-            .and_then(|line| Some(line.points))
-            .unwrap()
+    pub fn get_line_by_id(&self, id: u32) -> Result<(u32, u32)> {
+        self.get_line_reference_inmut(id).map(|line| line.points)
     }
 
-    pub fn remove_point_of_id(&mut self, id: u32, preserve: bool) {
-        for i in 0..self.points.len() {
-            if self.points[i].id == id {
-                self.points.remove(i);
-                break;
+    pub fn get_line_cords(&self, line: &Line) -> Result<(PointCords, PointCords)> {
+        Ok((self.get_point_by_id(line.points.0)?, self.get_point_by_id(line.points.1)?))
+    }
+
+    /// Removes a vertex by merging its two incident edges; every other edge is left untouched.
+    /// Returns the relations of other lines that have to be updated: (line, new related line).
+    pub fn remove_point_of_id(&mut self, id: u32) -> Result<Vec<(u32, Option<u32>)>> {
+        let index = self.points
+            .iter()
+            .position(|point| point.id == id)
+            .ok_or(EditorError::PointNotFound(id))?;
+        if !self.is_closed() && (index == 0 || index == self.points.len() - 1) {
+            return Ok(self.remove_path_end(index));
+        }
+        let min_points = self.get_min_points_count();
+        let incoming_index = self.lines
+            .iter()
+            .position(|line| line.points.1 == id)
+            .ok_or(EditorError::PointNotFound(id))?;
+        let outgoing_index = self.lines
+            .iter()
+            .position(|line| line.points.0 == id)
+            .ok_or(EditorError::PointNotFound(id))?;
+        let incoming = self.lines[incoming_index];
+        let outgoing = self.lines[outgoing_index];
+        let prev = self.get_point_by_id(incoming.points.0)?;
+        let current = self.get_point_by_id(id)?;
+        let next = self.get_point_by_id(outgoing.points.1)?;
+
+        self.points.remove(index);
+        if self.points.len() < min_points {
+            self.lines = vec![];
+            self.update_center();
+            return Ok([incoming.relation, outgoing.relation]
+                .iter()
+                .filter_map(|relation| relation.map(|line_id| (line_id, None)))
+                .collect());
+        }
+
+        let merged = merge_lines(&incoming, &outgoing, prev, current, next);
+        let mut relation_updates: Vec<(u32, Option<u32>)> = vec![];
+        for line in [incoming, outgoing].iter() {
+            match line.relation {
+                Some(line_id) if line_id == incoming.id || line_id == outgoing.id => {},
+                Some(line_id) if merged.relation == Some(line_id) => relation_updates.push((line_id, Some(merged.id))),
+                Some(line_id) => relation_updates.push((line_id, None)),
+                None => {}
             }
         }
-        self.lines = if preserve {
-            calculate_new_lines_preserving_relations(self.points.iter().collect(), self.lines.iter().collect())
-        } else {
-            calcualate_new_lines(self.points.iter().collect())
+
+        self.lines[incoming_index] = merged;
+        self.lines.remove(outgoing_index);
+        self.update_center();
+        Ok(relation_updates)
+    }
+
+    /// Parameter of the point of the line closest to p, measured along the flattened curve for Bézier lines.
+    /// None when the closest point is an end of the line.
+    pub fn get_split_parameter(&self, line_id: u32, p: PointCords) -> Result<Option<f64>> {
+        let line = self.get_line_reference_inmut(line_id)?;
+        let (p1, p2) = self.get_line_cords(line)?;
+        let t = match line.bezier {
+            Some((b1, b2)) => {
+                let samples: Vec<PointCords> = (0..=BEZIER_SEGMENTS)
+                    .map(|k| get_bezier_point(p1, b1, b2, p2, (k as f64)/(BEZIER_SEGMENTS as f64)))
+                    .collect();
+                let closest = (0..BEZIER_SEGMENTS as usize)
+                    .min_by(|a, b| get_distance_to_segment(p, samples[*a], samples[*a + 1])
+                        .total_cmp(&get_distance_to_segment(p, samples[*b], samples[*b + 1])))
+                    .unwrap_or(0);
+                ((closest as f64) + get_segment_parameter(p, samples[closest], samples[closest + 1]))/(BEZIER_SEGMENTS as f64)
+            },
+            None => get_segment_parameter(p, p1, p2)
+        };
+        Ok(if t > 0.0 && t < 1.0 {Some(t)} else {None})
+    }
+
+    /// Splits a line at parameter t, Bézier lines are subdivided exactly so the shape does not change.
+    /// The first part keeps the id and relation of the line, both parts keep its constant flag.
+    pub fn split_line(&mut self, line_id: u32, t: f64, ids: &mut IdAllocator) -> Result<u32> {
+        if t.is_nan() || t <= 0.0 || t >= 1.0 {
+            return Err(EditorError::InvalidSplit(line_id));
+        }
+        let line_index = self.lines
+            .iter()
+            .position(|line| line.id == line_id)
+            .ok_or(EditorError::LineNotFound(line_id))?;
+        let line = self.lines[line_index];
+        let (p1, p2) = self.get_line_cords(&line)?;
+        let point_index = self.points
+            .iter()
+            .position(|point| point.id == line.points.0)
+            .ok_or(EditorError::PointNotFound(line.points.0))?;
+
+        let (split_point, first_bezier, second_bezier) = match line.bezier {
+            Some((b1, b2)) => {
+                let (first, point, second) = split_bezier(p1, b1, b2, p2, t);
+                (point, Some(first), Some(second))
+            },
+            None => (get_point_on_line(p1, p2, t), None, None)
+        };
+        let point_id = ids.allocate();
+        self.points.insert(point_index + 1, Point { x: split_point.0, y: split_point.1, id: point_id });
+        self.lines[line_index] = Line {
+            points: (line.points.0, point_id),
+            length: get_line_length(p1, split_point),
+            bezier: first_bezier,
+            ..line
+        };
+        self.lines.insert(line_index + 1, Line {
+            points: (point_id, line.points.1),
+            length: get_line_length(split_point, p2),
+            id: ids.allocate(),
+            relation: None,
+            bezier: second_bezier,
+            ..line
+        });
+        self.update_center();
+        Ok(point_id)
+    }
+
+    pub fn split_line_into(&mut self, line_id: u32, parts: u32, ids: &mut IdAllocator) -> Result<Vec<u32>> {
+        let line = *self.get_line_reference_inmut(line_id)?;
+        let (p1, p2) = self.get_line_cords(&line)?;
+        let parameters: Vec<f64> = match line.bezier {
+            Some((b1, b2)) => get_bezier_parameters_by_length(p1, b1, b2, p2, parts),
+            None => (1..parts.max(1)).map(|part| (part as f64)/(parts as f64)).collect()
         };
 
-        self.center = get_centroid(&self.points);
+        let mut point_ids: Vec<u32> = vec![];
+        let mut current_line_id = line_id;
+        let mut done = 0.0;
+        for t in parameters {
+            let point_id = self.split_line(current_line_id, (t - done)/(1.0 - done), ids)?;
+            current_line_id = self.lines
+                .iter()
+                .find(|line| line.points.0 == point_id)
+                .map(|line| line.id)
+                .ok_or(EditorError::PointNotFound(point_id))?;
+            point_ids.push(point_id);
+            done = t;
+        }
+        Ok(point_ids)
     }
 
-    pub fn check_hover(&self, x: f64, y: f64) -> Option<PressedObject> {
-        if check_point_hover(self.center, PointCords(x,y)) {
+    pub fn check_hover(&self, x: f64, y: f64, scale: f64) -> Option<PressedObject> {
+        if check_point_hover(self.center, PointCords(x,y), scale) {
             return Some(PressedObject::Center);
         }
 
         for i in 0..self.points.len() {
-            if check_point_hover(PointCords(self.points[i].x, self.points[i].y), PointCords(x,y)) {
+            if check_point_hover(PointCords(self.points[i].x, self.points[i].y), PointCords(x,y), scale) {
                 return Some(PressedObject::Point(self.points[i].id));
             }
         }
@@ -80,18 +233,19 @@ impl Polygon {
         for i in 0..self.lines.len() {
             match self.lines[i].bezier {
                 Some((p1,p2)) => {
-                    if check_point_hover(p1, PointCords(x,y)) {
+                    if check_point_hover(p1, PointCords(x,y), scale) {
                         return Some(PressedObject::BesierLine(self.lines[i].id, 1));
                     }
-                    if check_point_hover(p2, PointCords(x,y)) {
+                    if check_point_hover(p2, PointCords(x,y), scale) {
                         return Some(PressedObject::BesierLine(self.lines[i].id, 2));
                     }
                 },
                 None => {
-                    let p1 = self.get_point_by_id(self.lines[i].points.0);
-                    let p2 = self.get_point_by_id(self.lines[i].points.1);
-                    if check_line_hover(p1, p2, PointCords(x,y)) {
-                        return Some(PressedObject::Line(self.lines[i].id, (x - p1.0, y - p1.1)));
+                    match self.get_line_cords(&self.lines[i]) {
+                        Ok((p1, p2)) if check_line_hover(p1, p2, PointCords(x,y), scale) => {
+                            return Some(PressedObject::Line(self.lines[i].id, (x - p1.0, y - p1.1)));
+                        },
+                        _ => {}
                     }
                 }
             }
@@ -100,23 +254,111 @@ impl Polygon {
         None
     }
 
-    pub fn recalculate(&mut self){
-        self.center = get_centroid(&self.points);
+    pub fn get_outline(&self) -> Vec<PointCords> {
+        let mut outline: Vec<PointCords> = self.lines
+            .iter()
+            .filter_map(|line| self.get_point_by_id(line.points.0).ok())
+            .collect();
+        if !self.is_closed() {
+            if let Some(Ok(end)) = self.lines.last().map(|line| self.get_point_by_id(line.points.1)) {
+                outline.push(end);
+            }
+        }
+        outline
+    }
 
-        for i in 0..self.lines.len() {
-            let p1 = self.get_point_by_id(self.lines[i].points.0);
-            let p2 = self.get_point_by_id(self.lines[i].points.1);
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        self.is_closed() && get_winding_number(&flatten_polygon(self), PointCords(x, y)) != 0
+    }
 
-            self.lines[i].length = get_line_length(p1, p2);
+    /// Adds the line from the last to the first vertex of an open path.
+    pub fn close(&mut self, ids: &mut IdAllocator) -> Result<()> {
+        if self.is_closed() || self.points.len() < 3 {
+            return Err(EditorError::InvalidShape("only open paths with at least 3 vertices can be closed"));
+        }
+        if !is_simple_ring(&self.get_outline()) {
+            return Err(EditorError::InvalidShape("closed outline would intersect itself"));
         }
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        self.lines.push(Line {
+            points: (last.id, first.id),
+            length: get_line_length(PointCords(last.x, last.y), PointCords(first.x, first.y)),
+            id: ids.allocate(),
+            is_const: false,
+            relation: None,
+            visited: false,
+            bezier: None
+        });
+        self.update_center();
+        Ok(())
     }
 
-    pub fn get_line_relation(&self, line_id: u32) -> Option<u32> {
-        self.lines
+    /// Turns a polygon into an open path by removing the given line, the path starts at the end of that line.
+    /// Returns the relations of other lines that have to be updated.
+    pub fn open_at(&mut self, line_id: u32) -> Result<Vec<(u32, Option<u32>)>> {
+        if !self.is_closed() {
+            return Err(EditorError::InvalidShape("path is already open"));
+        }
+        let line_index = self.lines
+            .iter()
+            .position(|line| line.id == line_id)
+            .ok_or(EditorError::LineNotFound(line_id))?;
+        let removed = self.lines.remove(line_index);
+        let start = self.points
             .iter()
-            .find(|line| line.id == line_id)
-            .unwrap()
-            .relation
+            .position(|point| point.id == removed.points.1)
+            .ok_or(EditorError::PointNotFound(removed.points.1))?;
+        self.points.rotate_left(start);
+        let lines_count = self.lines.len().max(1);
+        self.lines.rotate_left(line_index % lines_count);
+        self.update_center();
+        Ok(removed.relation.iter().map(|line_id| (*line_id, None)).collect())
+    }
+
+    fn remove_path_end(&mut self, index: usize) -> Vec<(u32, Option<u32>)> {
+        self.points.remove(index);
+        let removed = if index == 0 {self.lines.remove(0)} else {self.lines.remove(self.lines.len() - 1)};
+        if self.points.len() < 2 {
+            self.lines = vec![];
+        }
+        self.update_center();
+        removed.relation.iter().map(|line_id| (*line_id, None)).collect()
+    }
+
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.points
+            .iter_mut()
+            .for_each(|point| {
+                point.x += dx;
+                point.y += dy;
+            });
+
+        self.lines
+            .iter_mut()
+            .for_each(|line| {
+                if let Some((p1,p2)) = line.bezier {
+                    line.bezier = Some((
+                            PointCords(p1.0 + dx, p1.1 + dy),
+                            PointCords(p2.0 + dx, p2.1 + dy),
+                            ))
+                }
+            });
+
+        self.center = PointCords(self.center.0 + dx, self.center.1 + dy);
+    }
+
+    pub fn recalculate(&mut self){
+        self.update_center();
+
+        for i in 0..self.lines.len() {
+            if let Ok((p1, p2)) = self.get_line_cords(&self.lines[i]) {
+                self.lines[i].length = get_line_length(p1, p2);
+            }
+        }
+    }
+
+    pub fn get_line_relation(&self, line_id: u32) -> Result<Option<u32>> {
+        self.get_line_reference_inmut(line_id).map(|line| line.relation)
     }
 
     pub fn set_relation(&mut self, line_id: u32, related_line_id: Option<u32>){
@@ -149,8 +391,47 @@ impl Polygon {
     }
 }
 
+#[derive(Clone)]
 pub struct Polygon {
     pub points: Vec<Point>,
     pub lines: Vec<Line>,
-    pub center: PointCords
+    pub center: PointCords,
+    pub centroid_mode: CentroidMode,
+    pub dimensions: Vec<Dimension>,
+    pub layer: u32
+}
+
+// Merging rules for the two edges around a removed vertex: the merged edge keeps the id of the
+// incoming edge, stays constant only if both edges were constant, keeps a parallel relation only
+// when the removed vertex was collinear (so the direction does not change) and becomes a Bézier
+// curve with the outer control points when any of the two edges was curved.
+fn merge_lines(incoming: &Line, outgoing: &Line, prev: PointCords, current: PointCords, next: PointCords) -> Line {
+    let is_straight = incoming.bezier.is_none() && outgoing.bezier.is_none();
+    let keeps_direction = is_straight && is_collinear(prev, current, next);
+    let is_internal = |line_id: u32| line_id == incoming.id || line_id == outgoing.id;
+    let relation = if keeps_direction {
+        incoming.relation
+            .filter(|line_id| !is_internal(*line_id))
+            .or(outgoing.relation.filter(|line_id| !is_internal(*line_id)))
+    } else {
+        None
+    };
+    let bezier = if is_straight {
+        None
+    } else {
+        let (r1, r2) = get_bezier_cords((prev, next));
+        Some((
+            incoming.bezier.map_or(r1, |(b1, _)| b1),
+            outgoing.bezier.map_or(r2, |(_, b2)| b2)
+        ))
+    };
+    Line {
+        points: (incoming.points.0, outgoing.points.1),
+        length: get_line_length(prev, next),
+        id: incoming.id,
+        is_const: incoming.is_const && outgoing.is_const,
+        relation,
+        visited: false,
+        bezier
+    }
 }
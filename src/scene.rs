@@ -0,0 +1,373 @@
+use std::f64::consts::PI;
+use js_sys::{Array, JSON, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::data_models::*;
+use crate::dimensions::DimensionLayout;
+use crate::draw::DIMENSION_COLOR;
+use crate::error::{EditorError, Result};
+use crate::polygon::Polygon;
+use crate::units::{LengthUnit, Units};
+use crate::utils::flatten_polygon;
+
+/// A scene read from JSON.
+pub struct Scene {
+    pub polygons: Vec<Polygon>,
+    pub next_id: u32,
+    pub units: Units,
+    pub layers: Vec<Layer>
+}
+
+pub fn scene_to_json(polygons: &[Polygon], next_id: u32, units: &Units, layers: &[Layer]) -> String {
+    let polygons: Vec<String> = polygons
+        .iter()
+        .map(|polygon| {
+            let points: Vec<String> = polygon.points
+                .iter()
+                .map(|point| format!("{{\"id\":{},\"x\":{},\"y\":{}}}", point.id, point.x, point.y))
+                .collect();
+            let lines: Vec<String> = polygon.lines
+                .iter()
+                .map(|line| format!(
+                    "{{\"id\":{},\"points\":[{},{}],\"is_const\":{},\"relation\":{},\"bezier\":{}}}",
+                    line.id,
+                    line.points.0,
+                    line.points.1,
+                    line.is_const,
+                    line.relation.map_or("null".to_string(), |id| id.to_string()),
+                    line.bezier.map_or("null".to_string(), |(b1, b2)| format!("[[{},{}],[{},{}]]", b1.0, b1.1, b2.0, b2.1))
+                ))
+                .collect();
+            let centroid_mode = match polygon.centroid_mode {
+                CentroidMode::Area => "area",
+                CentroidMode::VertexMean => "vertex_mean"
+            };
+            let dimensions: Vec<String> = polygon.dimensions
+                .iter()
+                .filter(|dimension| polygon.is_valid_dimension(dimension))
+                .map(|dimension| match dimension {
+                    Dimension::Length(line_id) => format!("{{\"line\":{}}}", line_id),
+                    Dimension::Angle(point_id) => format!("{{\"point\":{}}}", point_id)
+                })
+                .collect();
+            format!(
+                "{{\"centroid_mode\":\"{}\",\"layer\":{},\"points\":[{}],\"lines\":[{}],\"dimensions\":[{}]}}",
+                centroid_mode, polygon.layer, points.join(","), lines.join(","), dimensions.join(",")
+            )
+        })
+        .collect();
+    let layers: Vec<String> = layers
+        .iter()
+        .map(|layer| format!(
+            "{{\"id\":{},\"name\":\"{}\",\"visible\":{},\"locked\":{}}}",
+            layer.id, escape_json(&layer.name), layer.visible, layer.locked
+        ))
+        .collect();
+    format!(
+        "{{\"next_id\":{},\"unit\":\"{}\",\"pixels_per_unit\":{},\"layers\":[{}],\"polygons\":[{}]}}",
+        next_id, units.unit.get_name(), units.pixels_per_unit, layers.join(","), polygons.join(",")
+    )
+}
+
+pub fn get_default_layers() -> Vec<Layer> {
+    vec![Layer { id: 0, name: "Layer 1".to_string(), visible: true, locked: false }]
+}
+
+pub fn escape_json(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            c if (c as u32) < 0x20 => format!("\\u{:04x}", c as u32),
+            c => c.to_string()
+        })
+        .collect()
+}
+
+const SVG_MARGIN: f64 = 10.0;
+
+/// The drawing keeps canvas pixels as user units, its width and height give the real size in the document unit.
+pub fn scene_to_svg(polygons: &[Polygon], units: &Units) -> String {
+    let layouts: Vec<DimensionLayout> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.get_dimension_layouts(units))
+        .collect();
+    let outlines: Vec<PointCords> = polygons
+        .iter()
+        .flat_map(flatten_polygon)
+        .chain(layouts.iter().flat_map(get_dimension_bounds))
+        .collect();
+    let (min_x, min_y, max_x, max_y) = outlines
+        .iter()
+        .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(min_x, min_y, max_x, max_y), p| (min_x.min(p.0), min_y.min(p.1), max_x.max(p.0), max_y.max(p.1)));
+    let (min_x, min_y, width, height) = if outlines.is_empty() {
+        (0.0, 0.0, 0.0, 0.0)
+    } else {
+        (min_x - SVG_MARGIN, min_y - SVG_MARGIN, max_x - min_x + 2.0*SVG_MARGIN, max_y - min_y + 2.0*SVG_MARGIN)
+    };
+
+    let mut paths: Vec<String> = polygons
+        .iter()
+        .filter(|polygon| !polygon.lines.is_empty())
+        .map(|polygon| {
+            let fill = if polygon.is_closed() {"#dddddd"} else {"none"};
+            format!("<path d=\"{}\" fill=\"{}\" stroke=\"black\" stroke-width=\"2\"/>", get_svg_path(polygon), fill)
+        })
+        .collect();
+    paths.extend(layouts.iter().map(get_svg_dimension));
+    let size_unit = match units.unit {
+        LengthUnit::Pixel => "",
+        unit => unit.get_name()
+    };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}{}\" height=\"{}{}\">\n{}\n</svg>\n",
+        min_x, min_y, width, height, units.to_units(width), size_unit, units.to_units(height), size_unit, paths.join("\n")
+    )
+}
+
+fn get_svg_path(polygon: &Polygon) -> String {
+    let mut commands: Vec<String> = vec![];
+    polygon.lines
+        .iter()
+        .filter_map(|line| polygon.get_line_cords(line).ok().map(|cords| (line, cords)))
+        .for_each(|(line, (p1, p2))| {
+            if commands.is_empty() {
+                commands.push(format!("M {} {}", p1.0, p1.1));
+            }
+            match line.bezier {
+                Some((b1, b2)) => commands.push(format!("C {} {} {} {} {} {}", b1.0, b1.1, b2.0, b2.1, p2.0, p2.1)),
+                None => commands.push(format!("L {} {}", p2.0, p2.1))
+            }
+        });
+    if polygon.is_closed() {
+        commands.push("Z".to_string());
+    }
+    commands.join(" ")
+}
+
+/// Points enclosing the annotation, the label is given some room around its position.
+fn get_dimension_bounds(layout: &DimensionLayout) -> Vec<PointCords> {
+    let mut points: Vec<PointCords> = layout.segments
+        .iter()
+        .flat_map(|(p1, p2)| vec![*p1, *p2])
+        .collect();
+    let (label, room) = (layout.label, SVG_MARGIN);
+    points.push(PointCords(label.0 - 2.0*room, label.1 - room));
+    points.push(PointCords(label.0 + 2.0*room, label.1 + room));
+    if let Some((center, radius, _, _)) = layout.arc {
+        points.push(PointCords(center.0 - radius, center.1 - radius));
+        points.push(PointCords(center.0 + radius, center.1 + radius));
+    }
+    points
+}
+
+fn get_svg_dimension(layout: &DimensionLayout) -> String {
+    let mut commands: Vec<String> = layout.segments
+        .iter()
+        .map(|(p1, p2)| format!("M {} {} L {} {}", p1.0, p1.1, p2.0, p2.1))
+        .collect();
+    if let Some((center, radius, start, sweep)) = layout.arc {
+        let end = start + sweep;
+        commands.push(format!(
+            "M {} {} A {} {} 0 {} 1 {} {}",
+            center.0 + radius*start.cos(), center.1 + radius*start.sin(),
+            radius, radius, if sweep > PI {1} else {0},
+            center.0 + radius*end.cos(), center.1 + radius*end.sin()
+        ));
+    }
+    format!(
+        "<g fill=\"none\" stroke=\"{}\" stroke-width=\"1\"><path d=\"{}\"/><text x=\"{}\" y=\"{}\" fill=\"{}\" stroke=\"none\" font-size=\"14\" font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text></g>",
+        DIMENSION_COLOR, commands.join(" "), layout.label.0, layout.label.1, DIMENSION_COLOR, layout.text
+    )
+}
+
+pub fn scene_from_json(json: &str) -> Result<Scene> {
+    let scene = JSON::parse(json).map_err(|_| EditorError::InvalidScene("malformed JSON"))?;
+    let next_id = get_id(&scene, "next_id")?;
+    // scenes saved without a unit are in pixels
+    let unit = LengthUnit::from_name(get_field(&scene, "unit")?.as_string().as_deref().unwrap_or("px"));
+    let units = Units::with_scale(unit, get_field(&scene, "pixels_per_unit")?.as_f64().unwrap_or(0.0));
+    let polygons = get_array(&scene, "polygons")?
+        .iter()
+        .map(|polygon| parse_polygon(&polygon))
+        .collect::<Result<Vec<Polygon>>>()?;
+
+    // polygons may share vertices and lines, an id is unique within a polygon and names one kind of object
+    let has_duplicate = polygons
+        .iter()
+        .any(|polygon| has_duplicates(polygon.points.iter().map(|point| point.id).collect())
+            || has_duplicates(polygon.lines.iter().map(|line| line.id).collect()));
+    if has_duplicate {
+        return Err(EditorError::InvalidScene("ids are not unique"));
+    }
+    let mut lines: Vec<&Line> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.lines.iter())
+        .collect();
+    lines.sort_by_key(|line| line.id);
+    lines.dedup_by(|a, b| a.id == b.id && (a.points == b.points || a.points == (b.points.1, b.points.0)));
+    let line_ids: Vec<u32> = lines.iter().map(|line| line.id).collect();
+    let point_ids: Vec<u32> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.points.iter().map(|point| point.id))
+        .collect();
+    if has_duplicates(line_ids.clone()) || point_ids.iter().any(|id| line_ids.contains(id)) {
+        return Err(EditorError::InvalidScene("ids are not unique"));
+    }
+    // a relation is stored on both of its lines, constraint solving relies on it
+    let has_invalid_relation = polygons
+        .iter()
+        .flat_map(|polygon| polygon.lines.iter())
+        .any(|line| line.relation.is_some_and(|id| id == line.id || !lines.iter().any(|other| other.id == id && other.relation == Some(line.id))));
+    if has_invalid_relation {
+        return Err(EditorError::InvalidScene("relation points to a missing line or is not mutual"));
+    }
+    // scenes saved before layers were added have all polygons on a single layer
+    let layers = get_field(&scene, "layers")?;
+    let layers = if layers.is_undefined() {
+        get_default_layers()
+    } else {
+        to_array(&layers)?
+            .iter()
+            .map(|layer| parse_layer(&layer))
+            .collect::<Result<Vec<Layer>>>()?
+    };
+    if layers.is_empty() || has_duplicates(layers.iter().map(|layer| layer.id).collect()) {
+        return Err(EditorError::InvalidScene("layers are missing or their ids are not unique"));
+    }
+    if polygons.iter().any(|polygon| !layers.iter().any(|layer| layer.id == polygon.layer)) {
+        return Err(EditorError::InvalidScene("polygon points to a missing layer"));
+    }
+    Ok(Scene { polygons, next_id, units, layers })
+}
+
+fn has_duplicates(mut ids: Vec<u32>) -> bool {
+    let ids_count = ids.len();
+    ids.sort();
+    ids.dedup();
+    ids.len() != ids_count
+}
+
+fn parse_polygon(value: &JsValue) -> Result<Polygon> {
+    let centroid_mode = match get_field(value, "centroid_mode")?.as_string().as_deref() {
+        Some("vertex_mean") => CentroidMode::VertexMean,
+        _ => CentroidMode::Area
+    };
+    let points = get_array(value, "points")?
+        .iter()
+        .map(|point| Ok(Point {
+            id: get_id(&point, "id")?,
+            x: get_number(&point, "x")?,
+            y: get_number(&point, "y")?
+        }))
+        .collect::<Result<Vec<Point>>>()?;
+    let lines = get_array(value, "lines")?
+        .iter()
+        .map(|line| parse_line(&line))
+        .collect::<Result<Vec<Line>>>()?;
+    let is_polygon = points.len() >= 3 && lines.len() == points.len();
+    let is_path = points.len() >= 2 && lines.len() + 1 == points.len();
+    if !is_polygon && !is_path {
+        return Err(EditorError::InvalidScene("shape needs a line per vertex (polygon) or one line less (open path)"));
+    }
+
+    let mut polygon = Polygon::new(points, lines, centroid_mode);
+    let layer = get_field(value, "layer")?;
+    polygon.layer = if layer.is_undefined() {0} else {to_id(&layer)?};
+    for line in polygon.lines.iter() {
+        polygon.get_line_cords(line).map_err(|_| EditorError::InvalidScene("line points to a missing vertex"))?;
+    }
+    // scenes saved before dimensions were added have no such field
+    let dimensions = get_field(value, "dimensions")?;
+    if !dimensions.is_undefined() {
+        polygon.dimensions = to_array(&dimensions)?
+            .iter()
+            .map(|dimension| parse_dimension(&dimension))
+            .collect::<Result<Vec<Dimension>>>()?;
+    }
+    if !polygon.dimensions.iter().all(|dimension| polygon.is_valid_dimension(dimension)) {
+        return Err(EditorError::InvalidScene("dimension points to a missing line or vertex"));
+    }
+    polygon.recalculate();
+    Ok(polygon)
+}
+
+fn parse_line(value: &JsValue) -> Result<Line> {
+    let points = get_array(value, "points")?;
+    let relation = get_field(value, "relation")?;
+    let bezier = get_field(value, "bezier")?;
+    Ok(Line {
+        id: get_id(value, "id")?,
+        points: (to_id(&points.get(0))?, to_id(&points.get(1))?),
+        length: 0.0,
+        is_const: get_field(value, "is_const")?.as_bool().unwrap_or(false),
+        relation: if relation.is_null() || relation.is_undefined() {None} else {Some(to_id(&relation)?)},
+        visited: false,
+        bezier: if bezier.is_null() || bezier.is_undefined() {
+            None
+        } else {
+            let bezier = to_array(&bezier)?;
+            Some((parse_cords(&bezier.get(0))?, parse_cords(&bezier.get(1))?))
+        }
+    })
+}
+
+fn parse_layer(value: &JsValue) -> Result<Layer> {
+    Ok(Layer {
+        id: get_id(value, "id")?,
+        name: get_field(value, "name")?.as_string().unwrap_or_default(),
+        visible: get_field(value, "visible")?.as_bool().unwrap_or(true),
+        locked: get_field(value, "locked")?.as_bool().unwrap_or(false)
+    })
+}
+
+fn parse_dimension(value: &JsValue) -> Result<Dimension> {
+    let line = get_field(value, "line")?;
+    if line.is_undefined() {
+        Ok(Dimension::Angle(get_id(value, "point")?))
+    } else {
+        Ok(Dimension::Length(to_id(&line)?))
+    }
+}
+
+fn parse_cords(value: &JsValue) -> Result<PointCords> {
+    let cords = to_array(value)?;
+    Ok(PointCords(to_number(&cords.get(0))?, to_number(&cords.get(1))?))
+}
+
+fn get_field(value: &JsValue, key: &str) -> Result<JsValue> {
+    Reflect::get(value, &JsValue::from_str(key)).map_err(|_| EditorError::InvalidScene("expected an object"))
+}
+
+fn get_number(value: &JsValue, key: &str) -> Result<f64> {
+    to_number(&get_field(value, key)?)
+}
+
+fn get_id(value: &JsValue, key: &str) -> Result<u32> {
+    to_id(&get_field(value, key)?)
+}
+
+fn get_array(value: &JsValue, key: &str) -> Result<Array> {
+    to_array(&get_field(value, key)?)
+}
+
+fn to_number(value: &JsValue) -> Result<f64> {
+    value.as_f64().ok_or(EditorError::InvalidScene("expected a number"))
+}
+
+/// The largest u32 is left out, the id allocator could not hand out anything after it.
+fn to_id(value: &JsValue) -> Result<u32> {
+    let id = to_number(value)?;
+    if id.fract() != 0.0 || id < 0.0 || id >= u32::MAX as f64 {
+        return Err(EditorError::InvalidScene("ids have to be whole numbers below 4294967295"));
+    }
+    Ok(id as u32)
+}
+
+fn to_array(value: &JsValue) -> Result<Array> {
+    if Array::is_array(value) {
+        Ok(Array::from(value))
+    } else {
+        Err(EditorError::InvalidScene("expected an array"))
+    }
+}
@@ -0,0 +1,107 @@
+use std::f64::consts::PI;
+
+use crate::data_models::*;
+use crate::error::{EditorError, Result};
+use crate::ids::IdAllocator;
+use crate::polygon::Polygon;
+use crate::utils::{calcualate_new_lines, get_line_length};
+
+// distance of the control points of a cubic quarter arc, relative to the radius
+const KAPPA: f64 = 0.5522847498;
+const MIN_SHAPE_SIZE: f64 = 1.0;
+
+/// Builds the shape spanned between the first and the second click: a regular polygon or an ellipse around
+/// the anchor, or a rectangle with the anchor and the point as opposite corners.
+pub fn create_shape(tool: ShapeTool, anchor: PointCords, p: PointCords, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Result<Polygon> {
+    match tool {
+        ShapeTool::RegularPolygon(sides) => get_regular_polygon(anchor, p, sides, ids, centroid_mode),
+        ShapeTool::Rectangle => get_rectangle(anchor, p, ids, centroid_mode),
+        ShapeTool::Ellipse => get_ellipse(anchor, (p.0 - anchor.0).abs(), (p.1 - anchor.1).abs(), ids, centroid_mode),
+        ShapeTool::Circle => {
+            let radius = get_line_length(anchor, p);
+            get_ellipse(anchor, radius, radius, ids, centroid_mode)
+        },
+        ShapeTool::Polygon | ShapeTool::Freehand => Err(EditorError::InvalidShape("shape is not spanned between two points"))
+    }
+}
+
+/// All sides but one have a constant length, so the sides stay equal while the last one lets the shape move.
+/// Opposite sides of a polygon with an even number of sides are parallel.
+fn get_regular_polygon(center: PointCords, vertex: PointCords, sides: u32, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Result<Polygon> {
+    if sides < 3 {
+        return Err(EditorError::InvalidShape("regular polygon needs at least 3 sides"));
+    }
+    let radius = get_line_length(center, vertex);
+    if radius < MIN_SHAPE_SIZE {
+        return Err(EditorError::InvalidShape("shape is too small"));
+    }
+    let start = (vertex.1 - center.1).atan2(vertex.0 - center.0);
+    let cords: Vec<PointCords> = (0..sides)
+        .map(|i| {
+            let angle = start + 2.0*PI*(i as f64)/(sides as f64);
+            PointCords(center.0 + radius*angle.cos(), center.1 + radius*angle.sin())
+        })
+        .collect();
+
+    let mut polygon = get_polygon(cords, ids, centroid_mode);
+    let count = polygon.lines.len();
+    polygon.lines
+        .iter_mut()
+        .take(count - 1)
+        .for_each(|line| line.is_const = true);
+    if count.is_multiple_of(2) {
+        (0..count/2).for_each(|i| set_parallel(&mut polygon, i, i + count/2));
+    }
+    Ok(polygon)
+}
+
+fn get_rectangle(a: PointCords, b: PointCords, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Result<Polygon> {
+    if (b.0 - a.0).abs() < MIN_SHAPE_SIZE || (b.1 - a.1).abs() < MIN_SHAPE_SIZE {
+        return Err(EditorError::InvalidShape("shape is too small"));
+    }
+    let mut polygon = get_polygon(vec![a, PointCords(b.0, a.1), b, PointCords(a.0, b.1)], ids, centroid_mode);
+    set_parallel(&mut polygon, 0, 2);
+    set_parallel(&mut polygon, 1, 3);
+    Ok(polygon)
+}
+
+/// Four cubic Bézier quarter arcs, the chords of opposite quarters are parallel and a circle keeps their lengths.
+fn get_ellipse(center: PointCords, rx: f64, ry: f64, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Result<Polygon> {
+    if rx < MIN_SHAPE_SIZE || ry < MIN_SHAPE_SIZE {
+        return Err(EditorError::InvalidShape("shape is too small"));
+    }
+    let cords = vec![
+        PointCords(center.0 + rx, center.1),
+        PointCords(center.0, center.1 + ry),
+        PointCords(center.0 - rx, center.1),
+        PointCords(center.0, center.1 - ry)
+    ];
+    let mut polygon = get_polygon(cords, ids, centroid_mode);
+    for i in 0..polygon.lines.len() {
+        let (p1, p2) = polygon.get_line_cords(&polygon.lines[i])?;
+        polygon.lines[i].bezier = Some((
+            PointCords(p1.0 + KAPPA*(p2.0 - center.0), p1.1 + KAPPA*(p2.1 - center.1)),
+            PointCords(p2.0 + KAPPA*(p1.0 - center.0), p2.1 + KAPPA*(p1.1 - center.1))
+        ));
+        polygon.lines[i].is_const = rx == ry && i > 0;
+    }
+    set_parallel(&mut polygon, 0, 2);
+    set_parallel(&mut polygon, 1, 3);
+    Ok(polygon)
+}
+
+fn get_polygon(cords: Vec<PointCords>, ids: &mut IdAllocator, centroid_mode: CentroidMode) -> Polygon {
+    let points: Vec<Point> = cords
+        .iter()
+        .map(|p| Point { x: p.0, y: p.1, id: ids.allocate() })
+        .collect();
+    let lines = calcualate_new_lines(points.iter().collect(), ids);
+    let mut polygon = Polygon::new(points, lines, centroid_mode);
+    polygon.recalculate();
+    polygon
+}
+
+fn set_parallel(polygon: &mut Polygon, i: usize, j: usize) {
+    polygon.lines[i].relation = Some(polygon.lines[j].id);
+    polygon.lines[j].relation = Some(polygon.lines[i].id);
+}
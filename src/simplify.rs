@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use crate::data_models::{PointCords, SimplifyMethod};
+use crate::error::{EditorError, Result};
+use crate::fitting::douglas_peucker;
+use crate::polygon::Polygon;
+use crate::utils::{cross, flatten_polygon, get_line_length, is_simple_ring};
+
+impl Polygon {
+    /// Removes vertices that do not change the outline by more than the tolerance.
+    /// Pinned vertices, ends of open paths and ends of constant, related or Bézier edges are always kept,
+    /// so the removed vertices only merge plain edges. Returns the number of removed vertices.
+    pub fn simplify(&mut self, method: SimplifyMethod, tolerance: f64, pinned: &HashSet<u32>) -> Result<usize> {
+        let order = self.get_outline_ids();
+        let cords = order
+            .iter()
+            .map(|id| self.get_point_by_id(*id))
+            .collect::<Result<Vec<PointCords>>>()?;
+        let is_pinned: Vec<bool> = order
+            .iter()
+            .enumerate()
+            .map(|(i, id)| pinned.contains(id)
+                || (!self.is_closed() && (i == 0 || i == order.len() - 1))
+                || self.lines.iter().any(|line| (line.points.0 == *id || line.points.1 == *id)
+                    && (line.is_const || line.relation.is_some() || line.bezier.is_some())))
+            .collect();
+
+        let kept = match method {
+            SimplifyMethod::DouglasPeucker => simplify_douglas_peucker(&cords, &is_pinned, tolerance, self.is_closed()),
+            SimplifyMethod::Visvalingam => simplify_visvalingam(&cords, &is_pinned, tolerance, self.is_closed(), self.get_min_points_count())
+        };
+        if kept.len() < self.get_min_points_count() {
+            return Err(EditorError::InvalidShape("tolerance is too large"));
+        }
+        let removed: Vec<u32> = order
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !kept.contains(i))
+            .map(|(_, id)| *id)
+            .collect();
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let mut simplified = self.clone();
+        for id in removed.iter() {
+            simplified.remove_point_of_id(*id)?;
+        }
+        // a polygon that was simple has to stay simple
+        if self.is_closed() && is_simple_ring(&flatten_polygon(self)) && !is_simple_ring(&flatten_polygon(&simplified)) {
+            return Err(EditorError::InvalidShape("simplified polygon crosses itself"));
+        }
+        *self = simplified;
+        Ok(removed.len())
+    }
+
+    fn get_outline_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.lines
+            .iter()
+            .map(|line| line.points.0)
+            .collect();
+        if !self.is_closed() {
+            if let Some(line) = self.lines.last() {
+                ids.push(line.points.1);
+            }
+        }
+        ids
+    }
+}
+
+/// Runs Ramer–Douglas–Peucker on every run between two pinned vertices. A ring without pinned vertices
+/// is split at two of its extreme vertices, which any simplification keeps.
+fn simplify_douglas_peucker(cords: &[PointCords], is_pinned: &[bool], tolerance: f64, is_closed: bool) -> Vec<usize> {
+    let n = cords.len();
+    let mut anchors: Vec<usize> = (0..n).filter(|i| is_pinned[*i]).collect();
+    if anchors.is_empty() {
+        let first = get_farthest(cords, 0);
+        let second = get_farthest(cords, first);
+        anchors = vec![first.min(second), first.max(second)];
+        anchors.dedup();
+    }
+    let runs = if is_closed {anchors.len()} else {anchors.len() - 1};
+    let mut kept: Vec<usize> = vec![];
+    for k in 0..runs {
+        let start = anchors[k];
+        let mut end = anchors[(k + 1) % anchors.len()];
+        if end <= start {
+            end += n;
+        }
+        let run: Vec<PointCords> = (start..=end).map(|i| cords[i % n]).collect();
+        let mut run_kept = douglas_peucker(&run, tolerance);
+        run_kept.pop();
+        kept.extend(run_kept.iter().map(|i| (start + i) % n));
+    }
+    if !is_closed {
+        kept.push(n - 1);
+    }
+    kept.sort();
+    kept.dedup();
+    kept
+}
+
+fn get_farthest(cords: &[PointCords], from: usize) -> usize {
+    (0..cords.len()).fold(from, |farthest, i| if get_line_length(cords[from], cords[i]) > get_line_length(cords[from], cords[farthest]) {i} else {farthest})
+}
+
+/// Visvalingam–Whyatt: repeatedly removes the vertex spanning the smallest triangle with its current
+/// neighbours while that area is below the square of the tolerance.
+fn simplify_visvalingam(cords: &[PointCords], is_pinned: &[bool], tolerance: f64, is_closed: bool, min_points: usize) -> Vec<usize> {
+    let mut kept: Vec<usize> = (0..cords.len()).collect();
+    while kept.len() > min_points {
+        let count = kept.len();
+        let smallest = (0..count)
+            .filter(|k| !is_pinned[kept[*k]] && (is_closed || (*k > 0 && *k < count - 1)))
+            .map(|k| {
+                let prev = cords[kept[(k + count - 1) % count]];
+                let next = cords[kept[(k + 1) % count]];
+                (k, cross(prev, cords[kept[k]], next).abs()/2.0)
+            })
+            .fold(None, |smallest: Option<(usize, f64)>, current| match smallest {
+                Some(smallest) if smallest.1 <= current.1 => Some(smallest),
+                _ => Some(current)
+            });
+        match smallest {
+            Some((k, area)) if area < tolerance*tolerance => {kept.remove(k);},
+            _ => break
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_models::{CentroidMode, Point};
+    use crate::ids::IdAllocator;
+    use crate::utils::{calcualate_new_lines, calculate_path_lines};
+
+    /// A 100 by 100 square with four points bent by half a unit along every side.
+    fn get_noisy_square(ids: &mut IdAllocator) -> Polygon {
+        let corners = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let mut points: Vec<Point> = vec![];
+        for k in 0..4 {
+            let (a, b) = (corners[k], corners[(k + 1) % 4]);
+            for i in 0..5 {
+                let t = (i as f64)/5.0;
+                let bend = match i {
+                    0 => 0.0,
+                    i if i % 2 == 1 => 0.5,
+                    _ => -0.5
+                };
+                // the bend is perpendicular to the side
+                let (dx, dy) = ((b.1 - a.1)/100.0*bend, (a.0 - b.0)/100.0*bend);
+                points.push(Point { x: a.0 + t*(b.0 - a.0) + dx, y: a.1 + t*(b.1 - a.1) + dy, id: ids.allocate() });
+            }
+        }
+        let lines = calcualate_new_lines(points.iter().collect(), ids);
+        Polygon::new(points, lines, CentroidMode::VertexMean)
+    }
+
+    fn get_corner_ids(polygon: &Polygon) -> Vec<u32> {
+        polygon.points.iter().step_by(5).map(|point| point.id).collect()
+    }
+
+    #[test]
+    fn both_methods_reduce_to_corners() {
+        for method in [SimplifyMethod::DouglasPeucker, SimplifyMethod::Visvalingam] {
+            let mut ids = IdAllocator::new();
+            let mut polygon = get_noisy_square(&mut ids);
+            let corners = get_corner_ids(&polygon);
+            assert_eq!(polygon.simplify(method, 8.0, &HashSet::new()), Ok(16));
+            assert_eq!(polygon.points.len(), 4);
+            assert_eq!(polygon.lines.len(), 4);
+            assert!(corners.iter().all(|id| polygon.get_point_by_id(*id).is_ok()));
+        }
+    }
+
+    #[test]
+    fn small_tolerance_keeps_everything() {
+        for method in [SimplifyMethod::DouglasPeucker, SimplifyMethod::Visvalingam] {
+            let mut ids = IdAllocator::new();
+            let mut polygon = get_noisy_square(&mut ids);
+            assert_eq!(polygon.simplify(method, 0.01, &HashSet::new()), Ok(0));
+            assert_eq!(polygon.points.len(), 20);
+        }
+    }
+
+    #[test]
+    fn constrained_vertices_survive() {
+        for method in [SimplifyMethod::DouglasPeucker, SimplifyMethod::Visvalingam] {
+            let mut ids = IdAllocator::new();
+            let mut polygon = get_noisy_square(&mut ids);
+            let pinned_id = polygon.points[1].id;
+            let const_line = polygon.lines.iter().position(|line| line.points.0 == polygon.points[6].id).unwrap();
+            let relation_line = polygon.lines.iter().position(|line| line.points.0 == polygon.points[11].id).unwrap();
+            let bezier_line = polygon.lines.iter().position(|line| line.points.0 == polygon.points[16].id).unwrap();
+            polygon.lines[const_line].is_const = true;
+            let other_id = polygon.lines[bezier_line].id;
+            polygon.lines[relation_line].relation = Some(other_id);
+            polygon.lines[bezier_line].bezier = Some((PointCords(85.0, 100.0), PointCords(82.0, 100.0)));
+            let kept: Vec<u32> = [&polygon.lines[const_line], &polygon.lines[relation_line], &polygon.lines[bezier_line]]
+                .iter()
+                .flat_map(|line| [line.points.0, line.points.1])
+                .chain([pinned_id])
+                .collect();
+
+            let removed = polygon.simplify(method, 8.0, &HashSet::from([pinned_id])).unwrap();
+            assert!(removed > 0);
+            assert!(kept.iter().all(|id| polygon.get_point_by_id(*id).is_ok()));
+            assert!(polygon.lines.iter().any(|line| line.is_const));
+            assert!(polygon.lines.iter().any(|line| line.relation == Some(other_id)));
+            assert!(polygon.lines.iter().any(|line| line.bezier.is_some()));
+        }
+    }
+
+    #[test]
+    fn open_path_keeps_its_ends() {
+        let mut ids = IdAllocator::new();
+        let points: Vec<Point> = (0..6)
+            .map(|i| Point { x: (i as f64)*10.0, y: if i % 2 == 0 {0.0} else {0.3}, id: ids.allocate() })
+            .collect();
+        let lines = calculate_path_lines(points.iter().collect(), &mut ids);
+        let mut path = Polygon::new(points.clone(), lines, CentroidMode::VertexMean);
+        for method in [SimplifyMethod::DouglasPeucker, SimplifyMethod::Visvalingam] {
+            let mut simplified = path.clone();
+            assert_eq!(simplified.simplify(method, 4.0, &HashSet::new()), Ok(4));
+            assert!(simplified.get_point_by_id(points[0].id).is_ok());
+            assert!(simplified.get_point_by_id(points[5].id).is_ok());
+        }
+        assert_eq!(path.simplify(SimplifyMethod::DouglasPeucker, 0.1, &HashSet::new()), Ok(0));
+    }
+}
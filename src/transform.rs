@@ -0,0 +1,135 @@
+use crate::data_models::PointCords;
+use crate::polygon::Polygon;
+
+pub const ROTATION_HANDLE_DISTANCE: f64 = 40.0;
+pub const SCALE_HANDLE_DISTANCE: f64 = 30.0;
+
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64
+}
+
+impl Transform {
+    fn around_pivot(a: f64, b: f64, c: f64, d: f64, pivot: PointCords) -> Transform {
+        Transform {
+            a,
+            b,
+            c,
+            d,
+            e: pivot.0 - a*pivot.0 - c*pivot.1,
+            f: pivot.1 - b*pivot.0 - d*pivot.1
+        }
+    }
+
+    pub fn translation(dx: f64, dy: f64) -> Transform {
+        Transform { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: dx, f: dy }
+    }
+
+    pub fn rotation(angle: f64, pivot: PointCords) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform::around_pivot(cos, sin, -sin, cos, pivot)
+    }
+
+    pub fn scale(sx: f64, sy: f64, pivot: PointCords) -> Transform {
+        Transform::around_pivot(sx, 0.0, 0.0, sy, pivot)
+    }
+
+    pub fn shear(kx: f64, ky: f64, pivot: PointCords) -> Transform {
+        Transform::around_pivot(1.0, ky, kx, 1.0, pivot)
+    }
+
+    pub fn mirror(axis_angle: f64, pivot: PointCords) -> Transform {
+        let (sin, cos) = (2.0*axis_angle).sin_cos();
+        Transform::around_pivot(cos, sin, sin, -cos, pivot)
+    }
+
+    pub fn apply(&self, p: PointCords) -> PointCords {
+        PointCords(self.a*p.0 + self.c*p.1 + self.e, self.b*p.0 + self.d*p.1 + self.f)
+    }
+}
+
+impl Polygon {
+    pub fn apply_transform(&mut self, transform: &Transform) {
+        self.points
+            .iter_mut()
+            .for_each(|point| {
+                let PointCords(x, y) = transform.apply(PointCords(point.x, point.y));
+                point.x = x;
+                point.y = y;
+            });
+
+        self.lines
+            .iter_mut()
+            .for_each(|line| {
+                line.bezier = line.bezier.map(|(b1, b2)| (transform.apply(b1), transform.apply(b2)));
+            });
+
+        self.recalculate();
+    }
+}
+
+pub fn get_rotation_handle(center: PointCords) -> PointCords {
+    PointCords(center.0, center.1 - ROTATION_HANDLE_DISTANCE)
+}
+
+pub fn get_scale_handle(center: PointCords) -> PointCords {
+    PointCords(center.0 + SCALE_HANDLE_DISTANCE, center.1 + SCALE_HANDLE_DISTANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    const EPSILON: f64 = 0.000000001;
+
+    fn assert_close(p: PointCords, expected: PointCords) {
+        assert!((p.0 - expected.0).abs() < EPSILON && (p.1 - expected.1).abs() < EPSILON,
+            "({}, {}) != ({}, {})", p.0, p.1, expected.0, expected.1);
+    }
+
+    #[test]
+    fn pivot_stays_in_place() {
+        let pivot = PointCords(3.0, -2.0);
+        let transforms = [
+            Transform::rotation(1.0, pivot),
+            Transform::scale(2.0, -0.5, pivot),
+            Transform::shear(0.3, 1.2, pivot),
+            Transform::mirror(0.7, pivot)
+        ];
+        for transform in transforms.iter() {
+            assert_close(transform.apply(pivot), pivot);
+        }
+    }
+
+    #[test]
+    fn transforms_around_pivot() {
+        let pivot = PointCords(1.0, 1.0);
+        assert_close(Transform::rotation(PI/2.0, pivot).apply(PointCords(2.0, 1.0)), PointCords(1.0, 2.0));
+        assert_close(Transform::scale(2.0, 3.0, pivot).apply(PointCords(2.0, 2.0)), PointCords(3.0, 4.0));
+        assert_close(Transform::shear(0.5, 0.0, pivot).apply(PointCords(1.0, 3.0)), PointCords(2.0, 3.0));
+        // mirroring across the horizontal axis through the pivot
+        assert_close(Transform::mirror(0.0, pivot).apply(PointCords(4.0, 3.0)), PointCords(4.0, -1.0));
+        assert_close(Transform::translation(2.0, -1.0).apply(PointCords(4.0, 3.0)), PointCords(6.0, 2.0));
+    }
+
+    #[test]
+    fn repeated_transforms_compose() {
+        let pivot = PointCords(-2.0, 5.0);
+        let p = PointCords(7.0, 1.5);
+        let twice = Transform::rotation(0.4, pivot).apply(Transform::rotation(0.8, pivot).apply(p));
+        assert_close(twice, Transform::rotation(1.2, pivot).apply(p));
+        let mirror = Transform::mirror(0.9, pivot);
+        assert_close(mirror.apply(mirror.apply(p)), p);
+        let back = Transform::scale(0.25, 2.0, pivot).apply(Transform::scale(4.0, 0.5, pivot).apply(p));
+        assert_close(back, p);
+        // two mirrors make a rotation by twice the angle between their axes
+        let mirrors = Transform::mirror(0.5, pivot).apply(Transform::mirror(0.2, pivot).apply(p));
+        assert_close(mirrors, Transform::rotation(0.6, pivot).apply(p));
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashSet;
+use crate::data_models::PointCords;
+use crate::polygon::Polygon;
+use crate::utils::{cross, flatten_polygon, get_signed_area};
+
+const EPSILON: f64 = 0.0000001;
+const MAX_FLIP_ITERATIONS: u32 = 1000;
+
+fn is_point_in_triangle(p: PointCords, a: PointCords, b: PointCords, c: PointCords) -> bool {
+    cross(a, b, p) >= -EPSILON && cross(b, c, p) >= -EPSILON && cross(c, a, p) >= -EPSILON
+}
+
+fn is_ear(outline: &[PointCords], indices: &[usize], i: usize) -> bool {
+    let prev = indices[(i + indices.len() - 1) % indices.len()];
+    let current = indices[i];
+    let next = indices[(i + 1) % indices.len()];
+    let (a, b, c) = (outline[prev], outline[current], outline[next]);
+
+    if cross(a, b, c) <= EPSILON {
+        return false;
+    }
+
+    !indices
+        .iter()
+        .filter(|index| **index != prev && **index != current && **index != next)
+        .any(|index| {
+            let p = outline[*index];
+            let is_corner = (p.0 - a.0).abs() < EPSILON && (p.1 - a.1).abs() < EPSILON
+                || (p.0 - b.0).abs() < EPSILON && (p.1 - b.1).abs() < EPSILON
+                || (p.0 - c.0).abs() < EPSILON && (p.1 - c.1).abs() < EPSILON;
+            !is_corner && is_point_in_triangle(p, a, b, c)
+        })
+}
+
+pub struct TriangleMesh {
+    pub vertices: Vec<PointCords>,
+    pub triangles: Vec<(usize, usize, usize)>
+}
+
+impl Polygon {
+    pub fn triangulate(&self, holes: &[Vec<PointCords>]) -> TriangleMesh {
+        triangulate_with_holes(&flatten_polygon(self), holes)
+    }
+}
+
+fn clip_ears(vertices: &[PointCords], mut indices: Vec<usize>) -> Vec<(usize, usize, usize)> {
+    let mut triangles = vec![];
+    if indices.len() < 3 {
+        return triangles;
+    }
+
+    while indices.len() > 3 {
+        let ear = (0..indices.len()).find(|i| is_ear(vertices, &indices, *i));
+        let i = match ear {
+            Some(i) => i,
+            None => {
+                let collinear = (0..indices.len()).find(|i| {
+                    let prev = indices[(i + indices.len() - 1) % indices.len()];
+                    let next = indices[(i + 1) % indices.len()];
+                    cross(vertices[prev], vertices[indices[*i]], vertices[next]).abs() <= EPSILON
+                });
+                match collinear {
+                    Some(i) => {indices.remove(i); continue;},
+                    None => 0
+                }
+            }
+        };
+        let prev = indices[(i + indices.len() - 1) % indices.len()];
+        let next = indices[(i + 1) % indices.len()];
+        triangles.push((prev, indices[i], next));
+        indices.remove(i);
+    }
+
+    if cross(vertices[indices[0]], vertices[indices[1]], vertices[indices[2]]).abs() > EPSILON {
+        triangles.push((indices[0], indices[1], indices[2]));
+    }
+    triangles
+}
+
+fn find_bridge_vertex(vertices: &[PointCords], ring: &[usize], m: PointCords) -> Option<usize> {
+    let mut closest: Option<(f64, usize)> = None;
+    for i in 0..ring.len() {
+        let p1 = vertices[ring[i]];
+        let p2 = vertices[ring[(i + 1) % ring.len()]];
+        if (p1.1 > m.1) == (p2.1 > m.1) {
+            continue;
+        }
+        let x = p1.0 + (m.1 - p1.1)*(p2.0 - p1.0)/(p2.1 - p1.1);
+        if x < m.0 {
+            continue;
+        }
+        let candidate = if p1.0 > p2.0 {i} else {(i + 1) % ring.len()};
+        match closest {
+            Some((closest_x, _)) if closest_x <= x => {},
+            _ => {closest = Some((x, candidate));}
+        }
+    }
+
+    let (x, mut bridge) = closest?;
+    let intersection = PointCords(x, m.1);
+    let p = vertices[ring[bridge]];
+    let (a, b, c) = if cross(m, intersection, p) > 0.0 {(m, intersection, p)} else {(m, p, intersection)};
+    let mut best_angle = f64::MAX;
+    for i in 0..ring.len() {
+        let v = vertices[ring[i]];
+        if ring[i] == ring[bridge] || !is_point_in_triangle(v, a, b, c) {
+            continue;
+        }
+        let prev = vertices[ring[(i + ring.len() - 1) % ring.len()]];
+        let next = vertices[ring[(i + 1) % ring.len()]];
+        if cross(prev, v, next) > 0.0 {
+            continue;
+        }
+        let angle = (v.1 - m.1).atan2(v.0 - m.0).abs();
+        if angle < best_angle {
+            best_angle = angle;
+            bridge = i;
+        }
+    }
+    Some(bridge)
+}
+
+fn is_in_circumcircle(a: PointCords, b: PointCords, c: PointCords, d: PointCords) -> bool {
+    let (ax, ay) = (a.0 - d.0, a.1 - d.1);
+    let (bx, by) = (b.0 - d.0, b.1 - d.1);
+    let (cx, cy) = (c.0 - d.0, c.1 - d.1);
+    (ax*ax + ay*ay)*(bx*cy - cx*by) - (bx*bx + by*by)*(ax*cy - cx*ay) + (cx*cx + cy*cy)*(ax*by - bx*ay) > EPSILON
+}
+
+fn rotate_triangle(triangle: (usize, usize, usize), edge: usize) -> (usize, usize, usize) {
+    match edge {
+        0 => triangle,
+        1 => (triangle.1, triangle.2, triangle.0),
+        _ => (triangle.2, triangle.0, triangle.1)
+    }
+}
+
+fn make_delaunay(vertices: &[PointCords], triangles: &mut [(usize, usize, usize)], constrained: &HashSet<(usize, usize)>) {
+    let mut flipped = true;
+    let mut iterations = 0;
+    while flipped && iterations < MAX_FLIP_ITERATIONS {
+        flipped = false;
+        iterations += 1;
+        for t in 0..triangles.len() {
+            for edge in 0..3 {
+                let (a, b, c) = rotate_triangle(triangles[t], edge);
+                if constrained.contains(&(a.min(b), a.max(b))) {
+                    continue;
+                }
+                let neighbour = (0..triangles.len())
+                    .filter(|u| *u != t)
+                    .find_map(|u| (0..3)
+                        .map(|e| rotate_triangle(triangles[u], e))
+                        .find(|(x, y, _)| *x == b && *y == a)
+                        .map(|(_, _, d)| (u, d)));
+                if let Some((u, d)) = neighbour {
+                    let (pa, pb, pc, pd) = (vertices[a], vertices[b], vertices[c], vertices[d]);
+                    if is_in_circumcircle(pa, pb, pc, pd) && cross(pa, pd, pc) > EPSILON && cross(pd, pb, pc) > EPSILON {
+                        triangles[t] = (a, d, c);
+                        triangles[u] = (d, b, c);
+                        flipped = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn triangulate_with_holes(outline: &[PointCords], holes: &[Vec<PointCords>]) -> TriangleMesh {
+    let mut vertices = outline.to_vec();
+    let mut constrained: HashSet<(usize, usize)> = HashSet::new();
+    let mut ring: Vec<usize> = (0..outline.len()).collect();
+    if get_signed_area(outline) < 0.0 {
+        ring.reverse();
+    }
+    for i in 0..outline.len() {
+        let j = (i + 1) % outline.len();
+        constrained.insert((i.min(j), i.max(j)));
+    }
+
+    let mut sorted_holes: Vec<&Vec<PointCords>> = holes.iter().filter(|hole| hole.len() >= 3).collect();
+    let max_x = |hole: &Vec<PointCords>| hole.iter().fold(f64::MIN, |max, p| max.max(p.0));
+    sorted_holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+
+    for hole in sorted_holes {
+        let offset = vertices.len();
+        let mut hole_ring: Vec<usize> = (offset..offset + hole.len()).collect();
+        if get_signed_area(hole) > 0.0 {
+            hole_ring.reverse();
+        }
+        vertices.extend(hole.iter());
+
+        let mut m = 0;
+        for i in 0..hole_ring.len() {
+            if vertices[hole_ring[i]].0 > vertices[hole_ring[m]].0 {
+                m = i;
+            }
+        }
+        let bridge = match find_bridge_vertex(&vertices, &ring, vertices[hole_ring[m]]) {
+            Some(bridge) => bridge,
+            None => continue
+        };
+
+        for i in 0..hole.len() {
+            let j = (i + 1) % hole.len();
+            constrained.insert((offset + i.min(j), offset + i.max(j)));
+        }
+
+        let mut spliced: Vec<usize> = ring[..=bridge].to_vec();
+        for k in 0..=hole_ring.len() {
+            spliced.push(hole_ring[(m + k) % hole_ring.len()]);
+        }
+        spliced.extend(ring[bridge..].iter());
+        ring = spliced;
+    }
+
+    let mut triangles = clip_ears(&vertices, ring);
+    make_delaunay(&vertices, &mut triangles, &constrained);
+    TriangleMesh { vertices, triangles }
+}
+
+pub fn meshes_to_json(meshes: &[TriangleMesh]) -> String {
+    let polygons: Vec<String> = meshes
+        .iter()
+        .map(|mesh| {
+            let vertices: Vec<String> = mesh.vertices
+                .iter()
+                .map(|PointCords(x, y)| format!("[{},{}]", x, y))
+                .collect();
+            let triangles: Vec<String> = mesh.triangles
+                .iter()
+                .map(|(a, b, c)| format!("[{},{},{}]", a, b, c))
+                .collect();
+            format!("{{\"vertices\":[{}],\"triangles\":[{}]}}", vertices.join(","), triangles.join(","))
+        })
+        .collect();
+    format!("{{\"polygons\":[{}]}}", polygons.join(","))
+}
+
+pub fn meshes_to_obj(meshes: &[TriangleMesh]) -> String {
+    let mut obj = String::new();
+    let mut offset = 1;
+    for (i, mesh) in meshes.iter().enumerate() {
+        obj.push_str(format!("o polygon{}\n", i + 1).as_str());
+        mesh.vertices
+            .iter()
+            .for_each(|PointCords(x, y)| obj.push_str(format!("v {} {} 0\n", x, y).as_str()));
+        mesh.triangles
+            .iter()
+            .for_each(|(a, b, c)| obj.push_str(format!("f {} {} {}\n", a + offset, b + offset, c + offset).as_str()));
+        offset += mesh.vertices.len();
+    }
+    obj
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_mesh_area(mesh: &TriangleMesh) -> f64 {
+        mesh.triangles
+            .iter()
+            .map(|(a, b, c)| cross(mesh.vertices[*a], mesh.vertices[*b], mesh.vertices[*c])/2.0)
+            .sum()
+    }
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<PointCords> {
+        vec![PointCords(x, y), PointCords(x + size, y), PointCords(x + size, y + size), PointCords(x, y + size)]
+    }
+
+    #[test]
+    fn concave_outline_in_both_orientations() {
+        let l_shape = vec![
+            PointCords(0.0, 0.0), PointCords(4.0, 0.0), PointCords(4.0, 1.0),
+            PointCords(1.0, 1.0), PointCords(1.0, 4.0), PointCords(0.0, 4.0)
+        ];
+        let reversed: Vec<PointCords> = l_shape.iter().rev().copied().collect();
+        for outline in [l_shape, reversed].iter() {
+            let mesh = triangulate_with_holes(outline, &[]);
+            assert_eq!(mesh.triangles.len(), outline.len() - 2);
+            // every triangle is counterclockwise, so the signed areas add up to the outline area
+            assert!((get_mesh_area(&mesh) - 7.0).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn holes_are_left_empty() {
+        let mut hole = square(1.0, 1.0, 2.0);
+        hole.reverse();
+        let mesh = triangulate_with_holes(&square(0.0, 0.0, 4.0), &[hole]);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert!((get_mesh_area(&mesh) - 12.0).abs() < EPSILON);
+        let covers_hole = mesh.triangles.iter().any(|(a, b, c)| {
+            let (a, b, c) = (mesh.vertices[*a], mesh.vertices[*b], mesh.vertices[*c]);
+            is_point_in_triangle(PointCords(2.0, 2.0), a, b, c)
+        });
+        assert!(!covers_hole);
+    }
+
+    #[test]
+    fn convex_outline_is_delaunay() {
+        let outline = vec![
+            PointCords(0.0, 0.0), PointCords(10.0, -1.0), PointCords(20.0, 0.0),
+            PointCords(21.0, 5.0), PointCords(10.0, 6.0), PointCords(-1.0, 5.0)
+        ];
+        let mesh = triangulate_with_holes(&outline, &[]);
+        for (a, b, c) in mesh.triangles.iter() {
+            let others = (0..outline.len()).filter(|d| d != a && d != b && d != c);
+            for d in others {
+                assert!(!is_in_circumcircle(outline[*a], outline[*b], outline[*c], outline[d]));
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_export_formats() {
+        let meshes = vec![
+            TriangleMesh { vertices: vec![PointCords(0.0, 0.0), PointCords(1.0, 0.0), PointCords(0.0, 1.0)], triangles: vec![(0, 1, 2)] },
+            TriangleMesh { vertices: vec![], triangles: vec![] },
+            TriangleMesh { vertices: vec![PointCords(2.0, 0.0), PointCords(3.0, 0.0), PointCords(2.0, 1.5)], triangles: vec![(0, 1, 2)] }
+        ];
+        assert_eq!(
+            meshes_to_json(&meshes),
+            "{\"polygons\":[{\"vertices\":[[0,0],[1,0],[0,1]],\"triangles\":[[0,1,2]]},\
+             {\"vertices\":[],\"triangles\":[]},\
+             {\"vertices\":[[2,0],[3,0],[2,1.5]],\"triangles\":[[0,1,2]]}]}"
+        );
+        // faces of later objects refer to the global, 1-based vertex numbers
+        assert_eq!(
+            meshes_to_obj(&meshes),
+            "o polygon1\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\no polygon2\no polygon3\nv 2 0 0\nv 3 0 0\nv 2 1.5 0\nf 4 5 6\n"
+        );
+    }
+}
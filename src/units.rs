@@ -0,0 +1,109 @@
+// CSS pixels per inch, used as the default scale of physical units
+const PIXELS_PER_INCH: f64 = 96.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Pixel,
+    Millimeter,
+    Centimeter,
+    Inch
+}
+
+impl LengthUnit {
+    pub fn from_name(name: &str) -> LengthUnit {
+        match name {
+            "mm" => LengthUnit::Millimeter,
+            "cm" => LengthUnit::Centimeter,
+            "in" => LengthUnit::Inch,
+            _ => LengthUnit::Pixel
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            LengthUnit::Pixel => "px",
+            LengthUnit::Millimeter => "mm",
+            LengthUnit::Centimeter => "cm",
+            LengthUnit::Inch => "in"
+        }
+    }
+
+    pub fn get_default_scale(&self) -> f64 {
+        match self {
+            LengthUnit::Pixel => 1.0,
+            LengthUnit::Millimeter => PIXELS_PER_INCH/25.4,
+            LengthUnit::Centimeter => PIXELS_PER_INCH/2.54,
+            LengthUnit::Inch => PIXELS_PER_INCH
+        }
+    }
+}
+
+/// Unit of the document: geometry is stored in canvas pixels and shown in the unit,
+/// a unit is `pixels_per_unit` pixels long.
+#[derive(Clone, Copy)]
+pub struct Units {
+    pub unit: LengthUnit,
+    pub pixels_per_unit: f64
+}
+
+impl Units {
+    pub fn new() -> Units {
+        Units { unit: LengthUnit::Pixel, pixels_per_unit: 1.0 }
+    }
+
+    /// A missing, non-positive or non-finite scale falls back to the default one of the unit,
+    /// pixels always have scale 1.
+    pub fn with_scale(unit: LengthUnit, pixels_per_unit: f64) -> Units {
+        let pixels_per_unit = match unit {
+            LengthUnit::Pixel => 1.0,
+            _ if pixels_per_unit.is_finite() && pixels_per_unit > 0.0 => pixels_per_unit,
+            _ => unit.get_default_scale()
+        };
+        Units { unit, pixels_per_unit }
+    }
+
+    pub fn to_units(&self, pixels: f64) -> f64 {
+        pixels/self.pixels_per_unit
+    }
+
+    pub fn to_pixels(&self, value: f64) -> f64 {
+        value*self.pixels_per_unit
+    }
+
+    pub fn format_length(&self, pixels: f64) -> String {
+        match self.unit {
+            LengthUnit::Pixel => format!("{:.1}", pixels),
+            _ => format!("{:.2} {}", self.to_units(pixels), self.unit.get_name())
+        }
+    }
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_scales_fall_back_to_the_unit_default() {
+        for scale in [0.0, -3.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let units = Units::with_scale(LengthUnit::Millimeter, scale);
+            assert_eq!(units.pixels_per_unit, LengthUnit::Millimeter.get_default_scale());
+        }
+        assert_eq!(Units::with_scale(LengthUnit::Centimeter, 10.0).pixels_per_unit, 10.0);
+        assert_eq!(Units::with_scale(LengthUnit::Pixel, 10.0).pixels_per_unit, 1.0);
+    }
+
+    #[test]
+    fn conversion_round_trips() {
+        let units = Units::with_scale(LengthUnit::Inch, 0.0);
+        assert_eq!(units.to_pixels(2.0), 192.0);
+        assert_eq!(units.to_units(units.to_pixels(1.25)), 1.25);
+        assert_eq!(units.format_length(48.0), "0.50 in");
+        assert_eq!(Units::default().format_length(12.345), "12.3");
+    }
+}
@@ -1,24 +1,26 @@
-use rand::Rng;
-use crate::{data_models::*, polygon::Polygon};
+use crate::{data_models::*, polygon::Polygon, ids::IdAllocator};
 
 const LINE_MARGIN: f64 = 15.0;
 const POINT_MARGIN: f64 = 200.0;
+const AREA_EPSILON: f64 = 0.000001;
+const EPSILON: f64 = 0.0000001;
+const COLLINEAR_TOLERANCE: f64 = 0.01;
+pub const BEZIER_SEGMENTS: u32 = 32;
 
-pub fn calcualate_new_lines(points: Vec<&Point>) -> Vec<Line>{
+pub fn calcualate_new_lines(points: Vec<&Point>, ids: &mut IdAllocator) -> Vec<Line>{
     if points.len() < 3 {
         return vec![];
     }
     let mut lines: Vec<Line> = vec![];
     let mut last_point= points.last().unwrap();
     let mut last_point_id = last_point.id;
-    let mut rng = rand::thread_rng();
     points
         .iter()
         .for_each(|point| {
             lines.push(Line {
                 points: (last_point_id, point.id),
                 length: get_line_length(PointCords(last_point.x, last_point.y), PointCords(point.x, point.y)),
-                id: rng.gen(),
+                id: ids.allocate(),
                 is_const: false,
                 relation: None,
                 visited: false,
@@ -30,38 +32,19 @@ pub fn calcualate_new_lines(points: Vec<&Point>) -> Vec<Line>{
     lines
 }
 
-pub fn calculate_new_lines_preserving_relations(points: Vec<&Point>, old_lines: Vec<&Line>) -> Vec<Line>{
-    if points.len() < 3 {
-        return vec![];
-    }
-    let mut lines: Vec<Line> = vec![];
-    let mut last_point= points.last().unwrap();
-    let mut last_point_id = last_point.id;
-    let mut rng = rand::thread_rng();
+pub fn calculate_path_lines(points: Vec<&Point>, ids: &mut IdAllocator) -> Vec<Line>{
     points
-        .iter()
-        .for_each(|point| {
-            let mut rel: Option<u32> =  None;
-            for i in 0..old_lines.len() {
-                if (old_lines[i].points.1 == last_point_id  && old_lines[i].points.0 == point.id) 
-                    || (old_lines[i].points.0 == last_point_id  && old_lines[i].points.1 == point.id) {
-                    rel = old_lines[i].relation;
-                    break;
-                }
-            }
-            lines.push(Line {
-                points: (last_point_id, point.id),
-                length: get_line_length(PointCords(last_point.x, last_point.y), PointCords(point.x, point.y)),
-                id: rng.gen(),
-                is_const: false,
-                relation: rel,
-                visited: false,
-                bezier: None
-            });
-            last_point = point;
-            last_point_id = point.id;
-        });
-    lines
+        .windows(2)
+        .map(|pair| Line {
+            points: (pair[0].id, pair[1].id),
+            length: get_line_length(PointCords(pair[0].x, pair[0].y), PointCords(pair[1].x, pair[1].y)),
+            id: ids.allocate(),
+            is_const: false,
+            relation: None,
+            visited: false,
+            bezier: None
+        })
+        .collect()
 }
 
 pub fn get_click_point(p1: PointCords, offset: (f64, f64)) -> PointCords {
@@ -74,22 +57,110 @@ pub fn get_line_length(p1: PointCords, p2: PointCords) -> f64{
     ((p1.0 - p2.0)*(p1.0 - p2.0)+(p1.1 - p2.1)*(p1.1 - p2.1)).sqrt()
 }
 
-pub fn check_point_hover(p1: PointCords, p2: PointCords) -> bool{
-    ((p1.0 - p2.0)*(p1.0 - p2.0) + (p1.1 - p2.1)*(p1.1 - p2.1)) < POINT_MARGIN
+pub fn check_point_hover(p1: PointCords, p2: PointCords, scale: f64) -> bool{
+    ((p1.0 - p2.0)*(p1.0 - p2.0) + (p1.1 - p2.1)*(p1.1 - p2.1))*scale*scale < POINT_MARGIN
+}
+
+pub fn check_line_hover(l1: PointCords, l2: PointCords,p: PointCords, scale: f64) -> bool {
+    let margin = LINE_MARGIN/scale;
+    if !(p.0 > l1.0.min(l2.0)-margin && p.0 < l1.0.max(l2.0)+margin && p.1 > l1.1.min(l2.1)-margin && p.1 < l1.1.max(l2.1)+margin) {
+        return false;
+    }
+    (((l2.0 - l1.0)*(l1.1 - p.1) - (l1.0-p.0)*(l2.1-l1.1)).abs())/(((l2.0 - l1.0)*(l2.0 - l1.0) + (l2.1 - l1.1)*(l2.1 - l1.1)).sqrt()) < margin
+}
+
+pub fn cross(o: PointCords, a: PointCords, b: PointCords) -> f64 {
+    (a.0 - o.0)*(b.1 - o.1) - (a.1 - o.1)*(b.0 - o.0)
+}
+
+pub fn get_signed_area(outline: &[PointCords]) -> f64 {
+    let mut area = 0.0;
+    for i in 0..outline.len() {
+        let p1 = outline[i];
+        let p2 = outline[(i + 1) % outline.len()];
+        area = area + p1.0*p2.1 - p2.0*p1.1;
+    }
+    area/2.0
+}
+
+pub fn get_segments_intersection(a: PointCords, b: PointCords, c: PointCords, d: PointCords) -> Option<(f64, f64, PointCords)> {
+    let r = PointCords(b.0 - a.0, b.1 - a.1);
+    let s = PointCords(d.0 - c.0, d.1 - c.1);
+    let denominator = r.0*s.1 - r.1*s.0;
+    if denominator.abs() < EPSILON {
+        return None;
+    }
+    let t = ((c.0 - a.0)*s.1 - (c.1 - a.1)*s.0)/denominator;
+    let u = ((c.0 - a.0)*r.1 - (c.1 - a.1)*r.0)/denominator;
+    if t <= EPSILON || t >= 1.0 - EPSILON || u <= EPSILON || u >= 1.0 - EPSILON {
+        return None;
+    }
+    Some((t, u, PointCords(a.0 + t*r.0, a.1 + t*r.1)))
+}
+
+pub fn get_projection_on_segment(p: PointCords, a: PointCords, b: PointCords) -> PointCords {
+    let length = (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2);
+    if length < EPSILON {
+        return a;
+    }
+    let t = (((p.0 - a.0)*(b.0 - a.0) + (p.1 - a.1)*(b.1 - a.1))/length).clamp(0.0, 1.0);
+    PointCords(a.0 + t*(b.0 - a.0), a.1 + t*(b.1 - a.1))
+}
+
+pub fn get_distance_to_segment(p: PointCords, a: PointCords, b: PointCords) -> f64 {
+    get_line_length(p, get_projection_on_segment(p, a, b))
+}
+
+pub fn is_simple_ring(outline: &[PointCords]) -> bool {
+    let n = outline.len();
+    if n < 3 || get_signed_area(outline).abs() < AREA_EPSILON {
+        return false;
+    }
+    for i in 0..n {
+        let prev = outline[(i + n - 1) % n];
+        let next = outline[(i + 1) % n];
+        if get_line_length(outline[i], next) < EPSILON {
+            return false;
+        }
+        let is_folded = cross(prev, outline[i], next).abs() < EPSILON
+            && (outline[i].0 - prev.0)*(next.0 - outline[i].0) + (outline[i].1 - prev.1)*(next.1 - outline[i].1) < 0.0;
+        if is_folded {
+            return false;
+        }
+    }
+    for i in 0..n {
+        for j in 0..n {
+            if j == i || (j + 1) % n == i {
+                continue;
+            }
+            if get_distance_to_segment(outline[i], outline[j], outline[(j + 1) % n]) < EPSILON {
+                return false;
+            }
+            if j > i + 1 && !(i == 0 && j == n - 1) && get_segments_intersection(outline[i], outline[(i + 1) % n], outline[j], outline[(j + 1) % n]).is_some() {
+                return false;
+            }
+        }
+    }
+    true
 }
 
-pub fn check_line_hover(l1: PointCords, l2: PointCords,p: PointCords) -> bool {
-    if !(p.0 > l1.0.min(l2.0)-LINE_MARGIN && p.0 < l1.0.max(l2.0)+LINE_MARGIN && p.1 > l1.1.min(l2.1)-LINE_MARGIN && p.1 < l1.1.max(l2.1)+LINE_MARGIN) {
+pub fn is_collinear(prev: PointCords, current: PointCords, next: PointCords) -> bool {
+    let lengths = get_line_length(prev, current)*get_line_length(current, next);
+    if lengths < EPSILON {
         return false;
     }
-    (((l2.0 - l1.0)*(l1.1 - p.1) - (l1.0-p.0)*(l2.1-l1.1)).abs())/(((l2.0 - l1.0)*(l2.0 - l1.0) + (l2.1 - l1.1)*(l2.1 - l1.1)).sqrt()) < LINE_MARGIN
+    let dot = (current.0 - prev.0)*(next.0 - current.0) + (current.1 - prev.1)*(next.1 - current.1);
+    dot > 0.0 && cross(prev, current, next).abs()/lengths < COLLINEAR_TOLERANCE
 }
 
 pub fn calculate_middle_point(l1: PointCords, l2: PointCords) -> PointCords {
     PointCords(l1.0.min(l2.0) + (l1.0 - l2.0).abs()/2.0, l1.1.min(l2.1) + (l1.1 - l2.1).abs()/2.0)
 }
 
-pub fn get_centroid(points: &Vec<Point>) -> PointCords {
+pub fn get_vertex_centroid(points: &[Point]) -> Option<PointCords> {
+    if points.is_empty() {
+        return None;
+    }
     let mut sum_x = 0.0;
     let mut sum_y = 0.0;
     points
@@ -98,35 +169,127 @@ pub fn get_centroid(points: &Vec<Point>) -> PointCords {
             sum_y = sum_y + point.y;
             sum_x = sum_x + point.x;
         });
-    PointCords(sum_x/f64::from(points.len() as u8),sum_y/f64::from(points.len() as u8))
-}
-
-pub fn get_new_split_lines(polygon: &Polygon, x: u32, y: u32, s: u32) -> (Line, Line) {
-    let mut rng = rand::thread_rng();
-    (Line {
-            id: rng.gen(),
-            points: (x, s),
-            length: get_line_length(
-                polygon.get_point_by_id(x),
-                polygon.get_point_by_id(s)
-            ),
-            is_const: false,
-            relation: None,
-            visited: false,
-            bezier: None
-        },
-        Line{
-            id: rng.gen(),
-            points: (s, y),
-            length: get_line_length(
-                polygon.get_point_by_id(s),
-                polygon.get_point_by_id(y)
-            ),
-            is_const: false,
-            relation: None,
-            visited: false,
-            bezier: None
+    Some(PointCords(sum_x/(points.len() as f64),sum_y/(points.len() as f64)))
+}
+
+pub fn get_area_centroid(outline: &[PointCords]) -> Option<PointCords> {
+    let mut area = 0.0;
+    let mut sum_x = 0.0;
+    let mut sum_y = 0.0;
+    for i in 0..outline.len() {
+        let p1 = outline[i];
+        let p2 = outline[(i + 1) % outline.len()];
+        let cross = p1.0*p2.1 - p2.0*p1.1;
+        area += cross;
+        sum_x += (p1.0 + p2.0)*cross;
+        sum_y += (p1.1 + p2.1)*cross;
+    }
+    if area.abs() < AREA_EPSILON {
+        return None;
+    }
+    Some(PointCords(sum_x/(3.0*area), sum_y/(3.0*area)))
+}
+
+pub fn get_centroid(polygon: &Polygon) -> Option<PointCords> {
+    match polygon.centroid_mode {
+        _ if !polygon.is_closed() => get_vertex_centroid(&polygon.points),
+        CentroidMode::VertexMean => get_vertex_centroid(&polygon.points),
+        CentroidMode::Area => get_area_centroid(&flatten_polygon(polygon))
+            .or_else(|| get_vertex_centroid(&polygon.points))
+    }
+}
+
+pub fn get_bezier_point(p1: PointCords, b1: PointCords, b2: PointCords, p2: PointCords, t: f64) -> PointCords {
+    let s = 1.0 - t;
+    PointCords(
+        s*s*s*p1.0 + 3.0*s*s*t*b1.0 + 3.0*s*t*t*b2.0 + t*t*t*p2.0,
+        s*s*s*p1.1 + 3.0*s*s*t*b1.1 + 3.0*s*t*t*b2.1 + t*t*t*p2.1
+    )
+}
+
+pub fn get_point_on_line(p1: PointCords, p2: PointCords, t: f64) -> PointCords {
+    PointCords(p1.0 + t*(p2.0 - p1.0), p1.1 + t*(p2.1 - p1.1))
+}
+
+pub fn get_segment_parameter(p: PointCords, a: PointCords, b: PointCords) -> f64 {
+    let length = (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2);
+    if length < EPSILON {
+        return 0.0;
+    }
+    (((p.0 - a.0)*(b.0 - a.0) + (p.1 - a.1)*(b.1 - a.1))/length).clamp(0.0, 1.0)
+}
+
+/// de Casteljau subdivision, returns control points of both halves and the split point.
+pub fn split_bezier(p1: PointCords, b1: PointCords, b2: PointCords, p2: PointCords, t: f64) -> ((PointCords, PointCords), PointCords, (PointCords, PointCords)) {
+    let q1 = get_point_on_line(p1, b1, t);
+    let q2 = get_point_on_line(b1, b2, t);
+    let q3 = get_point_on_line(b2, p2, t);
+    let r1 = get_point_on_line(q1, q2, t);
+    let r2 = get_point_on_line(q2, q3, t);
+    ((q1, r1), get_point_on_line(r1, r2, t), (r2, q3))
+}
+
+/// Parameters dividing the curve into parts of (approximately) equal arc length.
+pub fn get_bezier_parameters_by_length(p1: PointCords, b1: PointCords, b2: PointCords, p2: PointCords, parts: u32) -> Vec<f64> {
+    let samples = BEZIER_SEGMENTS*parts;
+    let mut lengths: Vec<f64> = vec![0.0];
+    let mut last = p1;
+    for k in 1..=samples {
+        let point = get_bezier_point(p1, b1, b2, p2, (k as f64)/(samples as f64));
+        lengths.push(lengths[lengths.len() - 1] + get_line_length(last, point));
+        last = point;
+    }
+    let total = lengths[lengths.len() - 1];
+    (1..parts)
+        .map(|part| {
+            let target = total*(part as f64)/(parts as f64);
+            let k = lengths.iter().position(|length| *length >= target).unwrap_or(lengths.len() - 1).max(1);
+            let segment = lengths[k] - lengths[k - 1];
+            let fraction = if segment < EPSILON {0.0} else {(target - lengths[k - 1])/segment};
+            ((k - 1) as f64 + fraction)/(samples as f64)
         })
+        .collect()
+}
+
+pub fn flatten_polygon(polygon: &Polygon) -> Vec<PointCords> {
+    let mut outline: Vec<PointCords> = vec![];
+    polygon.lines
+        .iter()
+        .for_each(|line| {
+            let (p1, p2) = match polygon.get_line_cords(line) {
+                Ok(cords) => cords,
+                Err(_) => return
+            };
+            outline.push(p1);
+            if let Some((b1, b2)) = line.bezier {
+                for i in 1..BEZIER_SEGMENTS {
+                    outline.push(get_bezier_point(p1, b1, b2, p2, (i as f64)/(BEZIER_SEGMENTS as f64)));
+                }
+            }
+        });
+    if !polygon.is_closed() {
+        if let Some(Ok(end)) = polygon.lines.last().map(|line| polygon.get_point_by_id(line.points.1)) {
+            outline.push(end);
+        }
+    }
+    outline
+}
+
+pub fn get_winding_number(outline: &[PointCords], p: PointCords) -> i32 {
+    let mut winding = 0;
+    for i in 0..outline.len() {
+        let p1 = outline[i];
+        let p2 = outline[(i + 1) % outline.len()];
+        let side = (p2.0 - p1.0)*(p.1 - p1.1) - (p.0 - p1.0)*(p2.1 - p1.1);
+        if p1.1 <= p.1 {
+            if p2.1 > p.1 && side > 0.0 {
+                winding += 1;
+            }
+        } else if p2.1 <= p.1 && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
 }
 
 pub fn check_if_parallel(l1: (PointCords, PointCords), l2: (PointCords, PointCords)) -> bool {